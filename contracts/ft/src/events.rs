@@ -0,0 +1,81 @@
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+use nep297::EventStandard;
+
+/// NEP-297 event standard and version for this contract
+pub const FT_STANDARD_NAME: &str = "nep141";
+pub const FT_METADATA_SPEC: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    FtMint(Vec<FtMintLog>),
+    FtTransfer(Vec<FtTransferLog>),
+    FtBurn(Vec<FtBurnLog>),
+}
+
+/// NEP-297 event log of this contract, see the nep297 crate
+pub type EventLog = nep297::EventLog<EventLogVariant>;
+
+impl EventStandard for EventLogVariant {
+    const STANDARD: &'static str = FT_STANDARD_NAME;
+    const VERSION: &'static str = FT_METADATA_SPEC;
+
+    fn log(message: &str) {
+        env::log(message.as_bytes());
+    }
+}
+
+/// An event log to capture tokens minting
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `amount`: the number of tokens to mint, wrapped in quotes and treated like a string
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMintLog {
+    pub owner_id: AccountId,
+    pub amount: U128,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture tokens transfer
+///
+/// Arguments
+/// * `old_owner_id`: "owner.near"
+/// * `new_owner_id`: "receiver.near"
+/// * `amount`: the number of tokens to transfer, wrapped in quotes and treated like a string
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtTransferLog {
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub amount: U128,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture tokens burning
+///
+/// Arguments
+/// * `owner_id`: owner of the burned tokens
+/// * `amount`: the number of tokens to burn, wrapped in quotes and treated like a string
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtBurnLog {
+    pub owner_id: AccountId,
+    pub amount: U128,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
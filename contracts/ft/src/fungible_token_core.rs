@@ -106,6 +106,7 @@ impl FungibleTokenCore for Contract {
     fn ft_transfer(&mut self, receiver_id: ValidAccountId, amount: U128, memo: Option<String>) {
         let sender_id = env::predecessor_account_id();
         assert_one_yocto();
+        self.assert_not_paused(PauseFlag::Transfers);
         let amount = amount.into();
         self.internal_transfer(&sender_id, receiver_id.as_ref(), amount, memo);
     }
@@ -119,6 +120,7 @@ impl FungibleTokenCore for Contract {
         memo: Option<String>,
     ) -> Promise {
         assert_one_yocto();
+        self.assert_not_paused(PauseFlag::Transfers);
         let sender_id = env::predecessor_account_id();
         let amount = amount.into();
         self.internal_transfer(&sender_id, receiver_id.as_ref(), amount, memo);
@@ -184,19 +186,21 @@ impl FungibleTokenResolver for Contract {
                 if let Some(sender_balance) = self.accounts.get(&sender_id) {
                     self.accounts
                         .insert(&sender_id, &(sender_balance + refund_amount));
-                    env::log(
-                        format!(
-                            "Refund {} from {} to {}",
-                            refund_amount, receiver_id, sender_id
-                        )
-                        .as_bytes(),
-                    );
+                    EventLog::new(EventLogVariant::FtTransfer(vec![FtTransferLog {
+                        old_owner_id: receiver_id,
+                        new_owner_id: sender_id,
+                        amount: refund_amount.into(),
+                        memo: Some("refund".to_string()),
+                    }])).emit();
                     return (amount - refund_amount).into();
                 } else {
                     // Sender's account was deleted, so we need to burn tokens.
                     self.total_supply -= refund_amount;
-                    env::log(b"The account of the sender was deleted");
-                    env::log(format!("Burn {}", refund_amount).as_bytes());
+                    EventLog::new(EventLogVariant::FtBurn(vec![FtBurnLog {
+                        owner_id: receiver_id,
+                        amount: refund_amount.into(),
+                        memo: Some("The account of the sender was deleted".to_string()),
+                    }])).emit();
                 }
             }
         }
@@ -20,10 +20,10 @@ impl Contract {
     pub(crate) fn internal_deposit(&mut self, account_id: &AccountId, amount: Balance) {
         let balance = self
             .accounts
-            .get(&account_id)
+            .get(account_id)
             .expect("The account is not registered");
         if let Some(new_balance) = balance.checked_add(amount) {
-            self.accounts.insert(&account_id, &new_balance);
+            self.accounts.insert(account_id, &new_balance);
         } else {
             env::panic(b"Balance overflow");
         }
@@ -32,10 +32,10 @@ impl Contract {
     pub(crate) fn internal_withdraw(&mut self, account_id: &AccountId, amount: Balance) {
         let balance = self
             .accounts
-            .get(&account_id)
+            .get(account_id)
             .expect("The account is not registered");
         if let Some(new_balance) = balance.checked_sub(amount) {
-            self.accounts.insert(&account_id, &new_balance);
+            self.accounts.insert(account_id, &new_balance);
         } else {
             env::panic(b"The account doesn't have enough balance");
         }
@@ -54,9 +54,11 @@ impl Contract {
         );
         self.internal_withdraw(sender_id, amount);
         self.internal_deposit(receiver_id, amount);
-        env::log(format!("Transfer {} from {} to {}", amount, sender_id, receiver_id).as_bytes());
-        if let Some(memo) = memo {
-            env::log(format!("Memo: {}", memo).as_bytes());
-        }
+        EventLog::new(EventLogVariant::FtTransfer(vec![FtTransferLog {
+            old_owner_id: sender_id.clone(),
+            new_owner_id: receiver_id.clone(),
+            amount: amount.into(),
+            memo,
+        }])).emit();
     }
 }
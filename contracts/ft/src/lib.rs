@@ -8,24 +8,36 @@
 * fungible_token_core.rs implements NEP-146 standard
 * storage_manager.rs implements NEP-145 standard for allocating storage per account
 * fungible_token_metadata.rs implements NEP-148 standard for providing token-specific metadata.
+* events.rs implements NEP-297 standard event logs for mint, transfer and burn.
+* pause.rs lets the owner and pausers pause minting or transfers.
+* upgrade.rs deploys new code and migrates versioned state.
+* vault.rs fractionalizes an NFT into shares of this token, with buyout and redeem.
 * internal.rs contains internal methods for fungible token.
 */
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::LookupMap;
+use near_sdk::collections::{LookupMap, UnorderedSet};
 use near_sdk::json_types::{U128, ValidAccountId};
 use near_sdk::{env, near_bindgen, AccountId, Balance, Promise, StorageUsage};
 
+pub use crate::events::*;
 pub use crate::fungible_token_core::*;
 pub use crate::fungible_token_metadata::*;
 use crate::internal::*;
+pub use crate::pause::*;
 pub use crate::storage_manager::*;
+pub use crate::upgrade::*;
+pub use crate::vault::*;
 use std::num::ParseIntError;
 use std::convert::TryInto;
 
+mod events;
 mod fungible_token_core;
 mod fungible_token_metadata;
 mod internal;
+mod pause;
 mod storage_manager;
+mod upgrade;
+mod vault;
 
 #[global_allocator]
 static ALLOC: near_sdk::wee_alloc::WeeAlloc<'_> = near_sdk::wee_alloc::WeeAlloc::INIT;
@@ -44,7 +56,14 @@ pub struct Contract {
     /// The storage size in bytes for one account.
     pub account_storage_usage: StorageUsage,
 
-    pub ft_metadata: FungibleTokenMetadata
+    pub ft_metadata: FungibleTokenMetadata,
+
+    /// CUSTOM - set when initialized with new_vault
+    pub vault: Option<Vault>,
+
+    /// CUSTOM - paused functionality and the accounts that can pause it besides the owner
+    pub paused: Vec<PauseFlag>,
+    pub pausers: UnorderedSet<AccountId>,
 }
 
 impl Default for Contract {
@@ -56,8 +75,44 @@ impl Default for Contract {
 #[near_bindgen]
 impl Contract {
     #[init]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(owner_id: ValidAccountId, total_supply: U128, version: String, name: String, symbol: String, reference: String, reference_hash: String, decimals: u8) -> Self {
         assert!(!env::state_exists(), "Already initialized");
+        let mut this = Self::internal_new(owner_id.as_ref(), version, name, symbol, reference, reference_hash, decimals);
+        this.total_supply = total_supply.into();
+        // Make owner have total supply
+        let total_supply_u128: u128 = total_supply.into();
+        this.accounts.insert(owner_id.as_ref(), &total_supply_u128);
+        EventLog::new(EventLogVariant::FtMint(vec![FtMintLog {
+            owner_id: owner_id.into(),
+            amount: total_supply,
+            memo: Some("Initial tokens supply is minted".to_string()),
+        }])).emit();
+        write_state_version();
+        this
+    }
+
+    /// Custom Methods
+
+    /// only owner can mint
+    pub fn mint(&mut self, amount: U128) {
+        assert!(env::predecessor_account_id() == self.owner_id, "must be owner_id");
+        assert!(self.vault.is_none(), "Vault shares have a fixed supply");
+        self.assert_not_paused(PauseFlag::Minting);
+        self.total_supply += u128::from(amount);
+        let mut balance = self.accounts.get(&self.owner_id).expect("owner should have balance");
+        balance += u128::from(amount);
+        self.accounts.insert(&self.owner_id, &balance);
+        EventLog::new(EventLogVariant::FtMint(vec![FtMintLog {
+            owner_id: self.owner_id.clone(),
+            amount,
+            memo: None,
+        }])).emit();
+    }
+}
+
+impl Contract {
+    fn internal_new(owner_id: &AccountId, version: String, name: String, symbol: String, reference: String, reference_hash: String, decimals: u8) -> Self {
         let ref_hash_result: Result<Vec<u8>, ParseIntError> = (0..reference_hash.len())
             .step_by(2)
             .map(|i| u8::from_str_radix(&reference_hash[i..i + 2], 16))
@@ -65,9 +120,9 @@ impl Contract {
         let ref_hash_fixed_bytes: [u8; 32] = ref_hash_result.unwrap().as_slice().try_into().unwrap();
 
         let mut this = Self {
-            owner_id: owner_id.clone().into(),
+            owner_id: owner_id.clone(),
             accounts: LookupMap::new(b"a".to_vec()),
-            total_supply: total_supply.into(),
+            total_supply: 0,
             account_storage_usage: 0,
             ft_metadata: FungibleTokenMetadata {
                 version,
@@ -76,7 +131,10 @@ impl Contract {
                 reference,
                 reference_hash: ref_hash_fixed_bytes,
                 decimals
-            }
+            },
+            vault: None,
+            paused: vec![],
+            pausers: UnorderedSet::new(b"p".to_vec()),
         };
         // Determine cost of insertion into LookupMap
         let initial_storage_usage = env::storage_usage();
@@ -84,29 +142,15 @@ impl Contract {
         this.accounts.insert(&tmp_account_id, &0u128);
         this.account_storage_usage = env::storage_usage() - initial_storage_usage;
         this.accounts.remove(&tmp_account_id);
-        // Make owner have total supply
-        let total_supply_u128: u128 = total_supply.into();
-        this.accounts.insert(&owner_id.as_ref(), &total_supply_u128);
         this
     }
-
-    /// Custom Methods
-
-    /// only owner can mint
-    pub fn mint(&mut self, amount: U128) {
-        assert!(env::predecessor_account_id() == self.owner_id, "must be owner_id");
-        self.total_supply += u128::from(amount);
-        let mut balance = self.accounts.get(&self.owner_id).expect("owner should have balance");
-        balance += u128::from(amount);
-        self.accounts.insert(&self.owner_id, &balance);
-    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 #[cfg(test)]
 mod fungible_token_tests {
     use near_sdk::MockedBlockchain;
-    use near_sdk::{testing_env, VMContext};
+    use near_sdk::{testing_env, PromiseOrValue, PromiseResult, VMContext};
 
     use super::*;
     use near_sdk::json_types::ValidAccountId;
@@ -166,8 +210,232 @@ mod fungible_token_tests {
         );
         assert_eq!(contract.ft_total_supply().0, 1_000_000_000_000_000);
         assert_eq!(contract.ft_balance_of(alice()).0, ZERO_U128);
-        assert_eq!(contract.ft_balance_of(bob().into()).0, ZERO_U128);
-        assert_eq!(contract.ft_balance_of(carol().into()).0, ZERO_U128);
+        assert_eq!(contract.ft_balance_of(bob()).0, ZERO_U128);
+        assert_eq!(contract.ft_balance_of(carol()).0, ZERO_U128);
+    }
+
+    fn nft() -> ValidAccountId {
+        ValidAccountId::try_from("nft.near").unwrap()
+    }
+
+    fn new_vault() -> Contract {
+        Contract::new_vault(
+            dex(),
+            U128::from(1_000),
+            String::from("0.1.0"),
+            String::from("Vault Shares"),
+            String::from("SHARE"),
+            String::from("https://example.com/vault"),
+            "7c879fa7b49901d0ecc6ff5d64d7f673da5e4a5eb52a8d50a214175760d8919a".to_string(),
+            0,
+            nft(),
+            U128::from(10u128.pow(24)),
+        )
+    }
+
+    #[test]
+    fn vault_mints_shares_for_the_nft() {
+        testing_env!(get_context(dex().into()));
+        let mut contract = new_vault();
+        assert_eq!(contract.ft_total_supply().0, 0);
+
+        // only the configured NFT contract is accepted
+        testing_env!(get_context(carol().into()));
+        let refund = contract.nft_on_transfer(dex().into(), dex().into(), "1".to_string(), String::new());
+        assert!(matches!(refund, PromiseOrValue::Value(true)));
+
+        testing_env!(get_context(nft().into()));
+        let refund = contract.nft_on_transfer(dex().into(), dex().into(), "1".to_string(), String::new());
+        assert!(matches!(refund, PromiseOrValue::Value(false)));
+        assert_eq!(contract.ft_total_supply().0, 1_000);
+        assert_eq!(contract.ft_balance_of(dex()).0, 1_000);
+        assert_eq!(contract.get_vault().unwrap().state, VaultState::Active);
+
+        // a second NFT is returned
+        let refund = contract.nft_on_transfer(dex().into(), dex().into(), "2".to_string(), String::new());
+        assert!(matches!(refund, PromiseOrValue::Value(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Must hold all shares to redeem")]
+    fn vault_redeem_requires_all_shares() {
+        testing_env!(get_context(dex().into()));
+        let mut contract = new_vault();
+        testing_env!(get_context(nft().into()));
+        contract.nft_on_transfer(dex().into(), dex().into(), "1".to_string(), String::new());
+        contract.accounts.insert(alice().as_ref(), &0);
+
+        let mut context = get_context(dex().into());
+        context.attached_deposit = 1;
+        testing_env!(context.clone());
+        contract.ft_transfer(alice(), U128::from(1), None);
+        testing_env!(context);
+        contract.redeem(None);
+    }
+
+    #[test]
+    fn vault_claims_proceeds_with_24_decimals() {
+        let shares = 1_000 * 10u128.pow(24);
+        let proceeds = 1_000 * 10u128.pow(24);
+        testing_env!(get_context(dex().into()));
+        let mut contract = Contract::new_vault(
+            dex(),
+            U128::from(shares),
+            String::from("0.1.0"),
+            String::from("Vault Shares"),
+            String::from("SHARE"),
+            String::from("https://example.com/vault"),
+            "7c879fa7b49901d0ecc6ff5d64d7f673da5e4a5eb52a8d50a214175760d8919a".to_string(),
+            24,
+            nft(),
+            U128::from(proceeds),
+        );
+        testing_env!(get_context(nft().into()));
+        contract.nft_on_transfer(dex().into(), dex().into(), "1".to_string(), String::new());
+        contract.accounts.insert(alice().as_ref(), &0);
+
+        let mut context = get_context(dex().into());
+        context.attached_deposit = 1;
+        testing_env!(context.clone());
+        contract.ft_transfer(alice(), U128::from(shares / 4), None);
+        let mut vault = contract.get_vault().unwrap();
+        vault.state = VaultState::BoughtOut;
+        vault.proceeds = U128::from(proceeds);
+        contract.vault = Some(vault);
+
+        testing_env!(context);
+        assert_eq!(contract.claim_buyout_proceeds().0, proceeds / 4 * 3);
+        let mut context = get_context(alice().into());
+        context.attached_deposit = 1;
+        testing_env!(context);
+        assert_eq!(contract.claim_buyout_proceeds().0, proceeds / 4);
+        assert_eq!(contract.ft_total_supply().0, 0);
+        assert_eq!(contract.get_vault().unwrap().proceeds.0, 0);
+    }
+
+    /// the JSON args of the nft_transfer the vault called
+    fn nft_transfer_args() -> near_sdk::serde_json::Value {
+        let receipts = env::BLOCKCHAIN_INTERFACE.with(|b| {
+            b.borrow().as_ref().unwrap().as_mocked_blockchain().unwrap().created_receipts().clone()
+        });
+        // deposits are u128, which serde_json::Value can't hold
+        let receipts: near_sdk::serde_json::Value =
+            near_sdk::serde_json::from_str(&near_sdk::serde_json::to_string(&receipts).unwrap()).unwrap();
+        let call = receipts.as_array().unwrap().iter()
+            .flat_map(|receipt| receipt["actions"].as_array().unwrap().clone())
+            .map(|action| action["FunctionCall"].clone())
+            .find(|call| call["method_name"] == "nft_transfer")
+            .expect("No nft_transfer");
+        near_sdk::serde_json::from_str(call["args"].as_str().unwrap()).unwrap()
+    }
+
+    /// the context of a callback whose promise returned result
+    fn callback_context(result: PromiseResult) {
+        let storage = env::take_blockchain_interface().unwrap().as_mut_mocked_blockchain().unwrap().take_storage();
+        env::set_blockchain_interface(Box::new(MockedBlockchain::new(
+            get_context("mike.near".to_string()),
+            Default::default(),
+            Default::default(),
+            vec![result],
+            storage,
+            Default::default(),
+        )));
+    }
+
+    fn active_vault() -> Contract {
+        testing_env!(get_context(dex().into()));
+        let mut contract = new_vault();
+        testing_env!(get_context(nft().into()));
+        contract.nft_on_transfer(dex().into(), dex().into(), "1".to_string(), String::new());
+        contract
+    }
+
+    #[test]
+    fn vault_buyout_transfers_the_nft() {
+        let mut contract = active_vault();
+        let mut context = get_context(alice().into());
+        context.attached_deposit = 10u128.pow(24);
+        testing_env!(context);
+        contract.buyout();
+        assert_eq!(contract.get_vault().unwrap().state, VaultState::Pending);
+        // nft-simple takes a u64 approval_id, owners pass 0
+        let args = nft_transfer_args();
+        assert_eq!(args["receiver_id"], "alice.near");
+        assert_eq!(args["token_id"], "1");
+        assert_eq!(args["approval_id"], 0);
+
+        callback_context(PromiseResult::Successful(vec![]));
+        contract.resolve_buyout(alice().into());
+        let vault = contract.get_vault().unwrap();
+        assert_eq!(vault.state, VaultState::BoughtOut);
+        assert_eq!(vault.proceeds.0, 10u128.pow(24));
+    }
+
+    #[test]
+    fn vault_failed_buyout_stays_active() {
+        let mut contract = active_vault();
+        let mut context = get_context(alice().into());
+        context.attached_deposit = 10u128.pow(24);
+        testing_env!(context);
+        contract.buyout();
+
+        callback_context(PromiseResult::Failed);
+        contract.resolve_buyout(alice().into());
+        let vault = contract.get_vault().unwrap();
+        assert_eq!(vault.state, VaultState::Active);
+        assert_eq!(vault.proceeds.0, 0);
+    }
+
+    #[test]
+    fn vault_redeem_transfers_the_nft() {
+        let mut contract = active_vault();
+        let mut context = get_context(dex().into());
+        context.attached_deposit = 1;
+        testing_env!(context);
+        contract.redeem(Some(carol()));
+        assert_eq!(contract.ft_total_supply().0, 0);
+        assert_eq!(contract.get_vault().unwrap().state, VaultState::Pending);
+        let args = nft_transfer_args();
+        assert_eq!(args["receiver_id"], "carol.near");
+        assert_eq!(args["approval_id"], 0);
+
+        callback_context(PromiseResult::Successful(vec![]));
+        contract.resolve_redeem(dex().into());
+        assert_eq!(contract.get_vault().unwrap().state, VaultState::Redeemed);
+    }
+
+    #[test]
+    fn vault_failed_redeem_restores_shares() {
+        let mut contract = active_vault();
+        let mut context = get_context(dex().into());
+        context.attached_deposit = 1;
+        testing_env!(context);
+        contract.redeem(None);
+
+        callback_context(PromiseResult::Failed);
+        contract.resolve_redeem(dex().into());
+        assert_eq!(contract.get_vault().unwrap().state, VaultState::Active);
+        assert_eq!(contract.ft_balance_of(dex()).0, 1_000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfers is paused")]
+    fn paused_transfers_fail() {
+        testing_env!(get_context(dex().into()));
+        let mut contract = new_vault();
+        contract.add_pauser(carol());
+        testing_env!(get_context(nft().into()));
+        contract.nft_on_transfer(dex().into(), dex().into(), "1".to_string(), String::new());
+        contract.accounts.insert(alice().as_ref(), &0);
+
+        testing_env!(get_context(carol().into()));
+        contract.pause(vec![PauseFlag::Transfers]);
+        assert!(contract.is_paused(PauseFlag::Transfers));
+
+        let mut context = get_context(dex().into());
+        context.attached_deposit = 1;
+        testing_env!(context);
+        contract.ft_transfer(alice(), U128::from(1), None);
     }
 
     #[test]
@@ -0,0 +1,73 @@
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// CUSTOM - functionality that can be paused in an emergency
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PauseFlag {
+    /// mint by the owner and vault shares minted for the deposited NFT
+    Minting,
+    /// ft_transfer and ft_transfer_call
+    Transfers,
+}
+
+/// CUSTOM - pausers stop minting or transfers until unpaused
+/// ft_resolve_transfer isn't paused so refunds of transfers already in flight still settle
+#[near_bindgen]
+impl Contract {
+    /// only owner
+
+    pub fn add_pauser(&mut self, account_id: ValidAccountId) -> bool {
+        assert!(env::predecessor_account_id() == self.owner_id, "must be owner_id");
+        self.pausers.insert(account_id.as_ref())
+    }
+
+    pub fn remove_pauser(&mut self, account_id: ValidAccountId) -> bool {
+        assert!(env::predecessor_account_id() == self.owner_id, "must be owner_id");
+        self.pausers.remove(account_id.as_ref())
+    }
+
+    /// only owner or pauser
+
+    pub fn pause(&mut self, flags: Vec<PauseFlag>) {
+        self.assert_pauser();
+        for flag in flags {
+            if !self.paused.contains(&flag) {
+                self.paused.push(flag);
+            }
+        }
+    }
+
+    pub fn unpause(&mut self, flags: Vec<PauseFlag>) {
+        self.assert_pauser();
+        self.paused.retain(|flag| !flags.contains(flag));
+    }
+
+    /// views
+
+    pub fn get_pausers(&self) -> Vec<AccountId> {
+        self.pausers.to_vec()
+    }
+
+    pub fn get_paused(&self) -> Vec<PauseFlag> {
+        self.paused.clone()
+    }
+
+    pub fn is_paused(&self, flag: PauseFlag) -> bool {
+        self.paused.contains(&flag)
+    }
+}
+
+impl Contract {
+    fn assert_pauser(&self) {
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == self.owner_id || self.pausers.contains(&predecessor_id),
+            "must be owner_id or pauser"
+        );
+    }
+
+    pub(crate) fn assert_not_paused(&self, flag: PauseFlag) {
+        assert!(!self.paused.contains(&flag), "{:?} is paused", flag);
+    }
+}
@@ -35,7 +35,7 @@ impl StorageManager for Contract {
         );
         let account_id = account_id
             .map(|a| a.into())
-            .unwrap_or_else(|| env::predecessor_account_id());
+            .unwrap_or_else(env::predecessor_account_id);
         if self.accounts.insert(&account_id, &0).is_some() {
             env::panic(b"The account is already registered");
         }
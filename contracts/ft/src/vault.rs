@@ -0,0 +1,285 @@
+use crate::*;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{ext_contract, Gas, PromiseOrValue, PromiseResult};
+
+/// proceeds * shares overflows u128 with 24 decimals
+/// the generated code trips assign_op_pattern
+#[allow(clippy::assign_op_pattern)]
+mod u256 {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+use u256::U256;
+
+const GAS_FOR_NFT_TRANSFER: Gas = 15_000_000_000_000;
+const GAS_FOR_RESOLVE_VAULT: Gas = 10_000_000_000_000;
+const NO_DEPOSIT: Balance = 0;
+
+pub type TokenId = String;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum VaultState {
+    /// waiting for the NFT
+    Empty,
+    /// holds the NFT, shares are minted
+    Active,
+    /// the NFT is being transferred out by a buyout or redeem
+    Pending,
+    /// the NFT was redeemed by a holder of all shares
+    Redeemed,
+    /// the NFT was bought, share holders claim the proceeds
+    BoughtOut,
+}
+
+/// CUSTOM - vault mode, the contract holds one NFT and its shares are this token
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Vault {
+    pub nft_contract_id: AccountId,
+    pub token_id: Option<TokenId>,
+    /// fixed supply minted to the owner when the NFT is received
+    pub shares: U128,
+    /// price in NEAR to buy the NFT out of the vault
+    pub reserve_price: U128,
+    pub state: VaultState,
+    /// NEAR from the buyout not yet claimed by share holders
+    pub proceeds: U128,
+}
+
+/// nft-simple requires an approval_id, owners pass 0
+#[ext_contract(ext_nft)]
+trait NonFungibleToken {
+    fn nft_transfer(&mut self, receiver_id: AccountId, token_id: TokenId, approval_id: u64, memo: Option<String>);
+}
+
+#[ext_contract(ext_vault_resolver)]
+trait VaultResolver {
+    fn resolve_buyout(&mut self, buyer_id: AccountId);
+    fn resolve_redeem(&mut self, account_id: AccountId);
+}
+
+pub trait NonFungibleTokenReceiver {
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool>;
+}
+
+#[near_bindgen]
+impl Contract {
+    /// vault mode, the owner sends the NFT with nft_transfer_call to receive all shares
+    #[init]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_vault(
+        owner_id: ValidAccountId,
+        shares: U128,
+        version: String,
+        name: String,
+        symbol: String,
+        reference: String,
+        reference_hash: String,
+        decimals: u8,
+        nft_contract_id: ValidAccountId,
+        reserve_price: U128,
+    ) -> Self {
+        assert!(!env::state_exists(), "Already initialized");
+        assert!(shares.0 > 0, "Shares must be positive");
+        let mut this = Self::internal_new(owner_id.as_ref(), version, name, symbol, reference, reference_hash, decimals);
+        this.accounts.insert(owner_id.as_ref(), &0);
+        this.vault = Some(Vault {
+            nft_contract_id: nft_contract_id.into(),
+            token_id: None,
+            shares,
+            reserve_price,
+            state: VaultState::Empty,
+            proceeds: U128(0),
+        });
+        write_state_version();
+        this
+    }
+
+    /// anyone can buy the NFT at the reserve price, the excess deposit is refunded
+    #[payable]
+    pub fn buyout(&mut self) -> Promise {
+        let buyer_id = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        let mut vault = self.internal_vault();
+        assert_eq!(vault.state, VaultState::Active, "Vault does not hold the NFT");
+        let reserve_price = u128::from(vault.reserve_price);
+        assert!(deposit >= reserve_price, "Must attach at least the reserve price of {}", reserve_price);
+        if deposit > reserve_price {
+            Promise::new(buyer_id.clone()).transfer(deposit - reserve_price);
+        }
+        vault.state = VaultState::Pending;
+        let token_id = vault.token_id.clone().unwrap();
+        let nft_contract_id = vault.nft_contract_id.clone();
+        self.vault = Some(vault);
+
+        ext_nft::nft_transfer(
+            buyer_id.clone(),
+            token_id,
+            0,
+            Some("vault buyout".to_string()),
+            &nft_contract_id,
+            1,
+            GAS_FOR_NFT_TRANSFER,
+        )
+        .then(ext_vault_resolver::resolve_buyout(
+            buyer_id,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_VAULT,
+        ))
+    }
+
+    /// after a buyout, burns the caller's shares for their part of the proceeds
+    #[payable]
+    pub fn claim_buyout_proceeds(&mut self) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut vault = self.internal_vault();
+        assert_eq!(vault.state, VaultState::BoughtOut, "Vault was not bought out");
+        let balance = self.accounts.get(&account_id).unwrap_or(0);
+        assert!(balance > 0, "No shares to claim");
+
+        let proceeds = u128::from(vault.proceeds);
+        let amount = (U256::from(proceeds) * U256::from(balance) / U256::from(self.total_supply)).as_u128();
+        self.internal_burn(&account_id, balance, "vault buyout proceeds claimed");
+        vault.proceeds = U128(proceeds - amount);
+        self.vault = Some(vault);
+        Promise::new(account_id).transfer(amount + 1);
+        amount.into()
+    }
+
+    /// a holder of all shares burns them to take the NFT, receiver_id defaults to the caller
+    #[payable]
+    pub fn redeem(&mut self, receiver_id: Option<ValidAccountId>) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut vault = self.internal_vault();
+        assert_eq!(vault.state, VaultState::Active, "Vault does not hold the NFT");
+        let balance = self.accounts.get(&account_id).unwrap_or(0);
+        assert!(balance > 0 && balance == self.total_supply, "Must hold all shares to redeem");
+
+        self.internal_burn(&account_id, balance, "vault redeemed");
+        vault.state = VaultState::Pending;
+        let token_id = vault.token_id.clone().unwrap();
+        let nft_contract_id = vault.nft_contract_id.clone();
+        self.vault = Some(vault);
+
+        ext_nft::nft_transfer(
+            receiver_id.map(|a| a.into()).unwrap_or_else(|| account_id.clone()),
+            token_id,
+            0,
+            Some("vault redeemed".to_string()),
+            &nft_contract_id,
+            1,
+            GAS_FOR_NFT_TRANSFER,
+        )
+        .then(ext_vault_resolver::resolve_redeem(
+            account_id,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_VAULT,
+        ))
+    }
+
+    /// self callback, refunds the buyer if the NFT could not be transferred
+    pub fn resolve_buyout(&mut self, buyer_id: AccountId) {
+        assert_self();
+        let mut vault = self.internal_vault();
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            vault.state = VaultState::BoughtOut;
+            vault.proceeds = vault.reserve_price;
+        } else {
+            vault.state = VaultState::Active;
+            Promise::new(buyer_id).transfer(vault.reserve_price.into());
+        }
+        self.vault = Some(vault);
+    }
+
+    /// self callback, mints the shares back if the NFT could not be transferred
+    pub fn resolve_redeem(&mut self, account_id: AccountId) {
+        assert_self();
+        let mut vault = self.internal_vault();
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            vault.state = VaultState::Redeemed;
+        } else {
+            vault.state = VaultState::Active;
+            self.internal_mint(&account_id, vault.shares.into(), "vault redeem failed, shares restored");
+        }
+        self.vault = Some(vault);
+    }
+
+    /// views
+
+    pub fn get_vault(&self) -> Option<Vault> {
+        self.vault.clone()
+    }
+}
+
+/// the owner deposits the NFT with nft_transfer_call, any other token is returned
+#[near_bindgen]
+impl NonFungibleTokenReceiver for Contract {
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        // the vault holds a single NFT, msg is not used
+        let _ = msg;
+        let mut vault = if let Some(vault) = self.vault.clone() {
+            vault
+        } else {
+            return PromiseOrValue::Value(true);
+        };
+        if env::predecessor_account_id() != vault.nft_contract_id
+            || vault.state != VaultState::Empty
+            || sender_id != previous_owner_id
+            || previous_owner_id != self.owner_id
+            || self.is_paused(PauseFlag::Minting)
+        {
+            return PromiseOrValue::Value(true);
+        }
+
+        vault.token_id = Some(token_id);
+        vault.state = VaultState::Active;
+        let shares = vault.shares.into();
+        self.vault = Some(vault);
+        self.internal_mint(&previous_owner_id, shares, "vault shares minted for the NFT");
+        PromiseOrValue::Value(false)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_vault(&self) -> Vault {
+        self.vault.clone().expect("Not a vault")
+    }
+
+    fn internal_mint(&mut self, account_id: &AccountId, amount: Balance, memo: &str) {
+        self.internal_deposit(account_id, amount);
+        self.total_supply += amount;
+        EventLog::new(EventLogVariant::FtMint(vec![FtMintLog {
+            owner_id: account_id.clone(),
+            amount: amount.into(),
+            memo: Some(memo.to_string()),
+        }])).emit();
+    }
+
+    fn internal_burn(&mut self, account_id: &AccountId, amount: Balance, memo: &str) {
+        self.internal_withdraw(account_id, amount);
+        self.total_supply -= amount;
+        EventLog::new(EventLogVariant::FtBurn(vec![FtBurnLog {
+            owner_id: account_id.clone(),
+            amount: amount.into(),
+            memo: Some(memo.to_string()),
+        }])).emit();
+    }
+}
@@ -0,0 +1,167 @@
+use crate::*;
+use nep297::EventStandard;
+
+/// NEP-297 event standard and version for this contract
+pub const MARKET_STANDARD_NAME: &str = "nft_market";
+pub const MARKET_EVENT_VERSION: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    SaleListed(Vec<SaleListedLog>),
+    SaleUpdated(Vec<SaleUpdatedLog>),
+    BidPlaced(Vec<BidLog>),
+    BidRefunded(Vec<BidLog>),
+    SaleCompleted(Vec<SaleCompletedLog>),
+    SaleRemoved(Vec<SaleRemovedLog>),
+    RentalListed(Vec<RentalListedLog>),
+    TokenRented(Vec<TokenRentedLog>),
+    RentalRemoved(Vec<SaleRemovedLog>),
+}
+
+/// NEP-297 event log of this contract, see the nep297 crate
+pub type EventLog = nep297::EventLog<EventLogVariant>;
+
+impl EventStandard for EventLogVariant {
+    const STANDARD: &'static str = MARKET_STANDARD_NAME;
+    const VERSION: &'static str = MARKET_EVENT_VERSION;
+
+    fn log(message: &str) {
+        env::log(message.as_bytes());
+    }
+}
+
+/// An event log to capture a new sale
+///
+/// Arguments
+/// * `owner_id`: "seller.near"
+/// * `nft_contract_id`: "nft.near"
+/// * `token_id`: "1"
+/// * `token_type`: optional token type of the listed token
+/// * `sale_conditions`: {"near": "1000"}
+/// * `is_auction`: true if the sale accepts bids
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleListedLog {
+    pub owner_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+
+    pub sale_conditions: SaleConditions,
+    pub is_auction: bool,
+}
+
+/// An event log to capture a price change
+///
+/// Arguments
+/// * `owner_id`: "seller.near"
+/// * `nft_contract_id`: "nft.near"
+/// * `token_id`: "1"
+/// * `sale_conditions`: {"near": "1000"}
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleUpdatedLog {
+    pub owner_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub sale_conditions: SaleConditions,
+}
+
+/// An event log to capture a bid being placed or refunded
+///
+/// Arguments
+/// * `bidder_id`: "buyer.near"
+/// * `nft_contract_id`: "nft.near"
+/// * `token_id`: "1"
+/// * `ft_token_id`: "near" or the FT contract of the bid
+/// * `price`: "1000"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BidLog {
+    pub bidder_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub ft_token_id: FungibleTokenId,
+    pub price: U128,
+}
+
+/// An event log to capture a completed sale
+///
+/// Arguments
+/// * `owner_id`: "seller.near"
+/// * `buyer_id`: "buyer.near"
+/// * `nft_contract_id`: "nft.near"
+/// * `token_id`: "1"
+/// * `ft_token_id`: "near" or the FT contract used to pay
+/// * `price`: "1000"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleCompletedLog {
+    pub owner_id: AccountId,
+    pub buyer_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub ft_token_id: FungibleTokenId,
+    pub price: U128,
+}
+
+/// An event log to capture a sale being removed without a purchase
+///
+/// Arguments
+/// * `owner_id`: "seller.near"
+/// * `nft_contract_id`: "nft.near"
+/// * `token_id`: "1"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleRemovedLog {
+    pub owner_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+}
+
+/// An event log to capture a new rental listing
+///
+/// Arguments
+/// * `owner_id`: "owner.near"
+/// * `nft_contract_id`: "nft.near"
+/// * `token_id`: "1"
+/// * `price_per_period`: "1000" in NEAR
+/// * `period`: length of a period in ms
+/// * `max_periods`: periods that can be rented at once
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RentalListedLog {
+    pub owner_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub price_per_period: U128,
+    pub period: U64,
+    pub max_periods: u64,
+}
+
+/// An event log to capture a token being rented
+///
+/// Arguments
+/// * `owner_id`: "owner.near"
+/// * `renter_id`: "renter.near"
+/// * `nft_contract_id`: "nft.near"
+/// * `token_id`: "1"
+/// * `price`: "1000" in NEAR
+/// * `expires`: ms timestamp the rental ends
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenRentedLog {
+    pub owner_id: AccountId,
+    pub renter_id: AccountId,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub price: U128,
+    pub expires: U64,
+}
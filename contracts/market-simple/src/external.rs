@@ -4,19 +4,40 @@ use crate::*;
 
 #[ext_contract(ext_contract)]
 trait ExtContract {
-    fn nft_transfer_payout(
-        &mut self,
-        receiver_id: AccountId,
-        token_id: TokenId,
-        approval_id: u64,
-        memo: String,
-        balance: U128,
-		max_len_payout: u32,
-    );
     fn ft_transfer(
         &mut self,
         receiver_id: AccountId,
         amount: U128,
         memo: Option<String>
     );
-}
\ No newline at end of file
+}
+
+/// ext_contract drops method attributes and adds the receiver, deposit and gas args,
+/// so the payout calls get their own module to scope the lint
+#[allow(clippy::too_many_arguments)]
+mod payout {
+    use crate::*;
+
+    #[ext_contract(ext_nft_payout)]
+    trait ExtNftPayout {
+        fn nft_transfer_payout(
+            &mut self,
+            receiver_id: AccountId,
+            token_id: TokenId,
+            approval_id: u64,
+            memo: String,
+            balance: U128,
+            max_len_payout: u32,
+        );
+        fn nft_set_user_payout(
+            &mut self,
+            token_id: TokenId,
+            user_id: AccountId,
+            expires: U64,
+            approval_id: u64,
+            balance: U128,
+            max_len_payout: u32,
+        );
+    }
+}
+pub use payout::*;
@@ -9,6 +9,8 @@ trait FungibleTokenReceiver {
 #[near_bindgen]
 impl FungibleTokenReceiver for Contract {
     fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        // the FT contract refunds the whole amount when this panics
+        self.assert_not_paused(PauseFlag::Purchases);
         let PurchaseArgs {
             nft_contract_id,
             token_id,
@@ -1,4 +1,5 @@
 use crate::*;
+use near_sdk::promise_result_as_success;
 
 pub(crate) fn hash_account_id(account_id: &AccountId) -> CryptoHash {
     let mut hash = CryptoHash::default();
@@ -6,6 +7,28 @@ pub(crate) fn hash_account_id(account_id: &AccountId) -> CryptoHash {
     hash
 }
 
+/// payout returned by nft_transfer_payout or nft_set_user_payout, None means a bad payout from a bad NFT contract
+/// payouts should add up to price, NFT contracts that round each amount down are allowed and the seller receives the dust
+pub(crate) fn payout_from_result(price: Balance, seller_id: &AccountId, max_len_payout: usize) -> Option<HashMap<AccountId, U128>> {
+    let value = promise_result_as_success()?;
+    let Payout { mut payout } = near_sdk::serde_json::from_slice::<Payout>(&value).ok()?;
+    if payout.len() > max_len_payout || payout.is_empty() {
+        env::log(format!("Cannot have more than {} royalties and sale.bids refunds", MAX_PAYOUTS_AND_REFUNDS).as_bytes());
+        return None;
+    }
+    let mut remainder = price;
+    for &value in payout.values() {
+        remainder = remainder.checked_sub(value.0)?;
+    }
+    if remainder > payout.len() as u128 {
+        return None;
+    }
+    if remainder > 0 {
+        payout.entry(seller_id.clone()).or_insert(U128(0)).0 += remainder;
+    }
+    Some(payout)
+}
+
 impl Contract {
     pub(crate) fn assert_owner(&self) {
         assert_eq!(
@@ -15,13 +38,20 @@ impl Contract {
         );
     }
 
+    /// sales and rentals of the account, each needs STORAGE_PER_SALE
+    pub(crate) fn internal_supply_listings_by_owner_id(&self, account_id: &AccountId) -> u64 {
+        self.by_owner_id.get(account_id).map(|s| s.len()).unwrap_or_default()
+            + self.rentals_by_owner_id.get(account_id).map(|s| s.len()).unwrap_or_default()
+    }
+
     /// refund the last bid of each token type, don't update sale because it's already been removed
 
     pub(crate) fn refund_all_bids(
         &mut self,
-        bids: &Bids,
+        sale: &Sale,
     ) {
-        for (bid_ft, bid_vec) in bids {
+        let mut refunds = vec![];
+        for (bid_ft, bid_vec) in &sale.bids {
             let bid = &bid_vec[bid_vec.len()-1];
             if bid_ft == "near" {
                     Promise::new(bid.owner_id.clone()).transfer(u128::from(bid.price));
@@ -35,6 +65,16 @@ impl Contract {
                     GAS_FOR_FT_TRANSFER,
                 );
             }
+            refunds.push(BidLog {
+                bidder_id: bid.owner_id.clone(),
+                nft_contract_id: sale.nft_contract_id.clone(),
+                token_id: sale.token_id.clone(),
+                ft_token_id: bid_ft.clone(),
+                price: bid.price,
+            });
+        }
+        if !refunds.is_empty() {
+            EventLog::new(EventLogVariant::BidRefunded(refunds)).emit();
         }
     }
 
@@ -9,17 +9,25 @@ use near_sdk::{
 use std::cmp::min;
 use std::collections::HashMap;
 
+use crate::events::*;
 use crate::external::*;
 use crate::internal::*;
+use crate::pause::*;
+use crate::rental::*;
 use crate::sale::*;
+use crate::upgrade::*;
 use near_sdk::env::STORAGE_PRICE_PER_BYTE;
 
+mod events;
 mod external;
 mod ft_callbacks;
 mod internal;
 mod nft_callbacks;
+mod pause;
+mod rental;
 mod sale;
 mod sale_views;
+mod upgrade;
 
 near_sdk::setup_alloc!();
 
@@ -30,8 +38,12 @@ const GAS_FOR_FT_TRANSFER: Gas = 5_000_000_000_000;
 const GAS_FOR_ROYALTIES: Gas = 115_000_000_000_000;
 const GAS_FOR_NFT_TRANSFER: Gas = 15_000_000_000_000;
 const BID_HISTORY_LENGTH_DEFAULT: u8 = 1;
+/// gas to do 10 FT transfers (and definitely 10 NEAR transfers) for payouts and bid refunds
+const MAX_PAYOUTS_AND_REFUNDS: usize = 10;
 const NO_DEPOSIT: Balance = 0;
 const STORAGE_PER_SALE: u128 = 1000 * STORAGE_PRICE_PER_BYTE;
+/// paid by the renter for the user record on the NFT contract, which refunds them what it doesn't use
+const STORAGE_FOR_RENTAL_USER: u128 = 350 * STORAGE_PRICE_PER_BYTE;
 static DELIMETER: &str = "||";
 
 pub type SaleConditions = HashMap<FungibleTokenId, U128>;
@@ -40,8 +52,12 @@ pub type TokenId = String;
 pub type TokenType = Option<String>;
 pub type FungibleTokenId = AccountId;
 pub type ContractAndTokenId = String;
-// TODO: Capital U128
-pub type Payout = HashMap<AccountId, U128>;
+/// NEP-199 payout returned by nft_transfer_payout
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
 #[derive(Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct StorageBalanceBounds {
@@ -60,6 +76,10 @@ pub struct Contract {
     pub ft_token_ids: UnorderedSet<AccountId>,
     pub storage_deposits: LookupMap<AccountId, Balance>,
     pub bid_history_length: u8,
+    pub rentals: UnorderedMap<ContractAndTokenId, Rental>,
+    pub rentals_by_owner_id: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    pub paused: Vec<PauseFlag>,
+    pub pausers: UnorderedSet<AccountId>,
 }
 
 /// Helper structure to for keys of the persistent collections.
@@ -74,6 +94,10 @@ pub enum StorageKey {
     ByNFTTokenTypeInner { token_type_hash: CryptoHash },
     FTTokenIds,
     StorageDeposits,
+    Rentals,
+    RentalsByOwnerId,
+    RentalsByOwnerIdInner { account_id_hash: CryptoHash },
+    Pausers,
 }
 
 #[near_bindgen]
@@ -89,6 +113,10 @@ impl Contract {
             ft_token_ids: UnorderedSet::new(StorageKey::FTTokenIds),
             storage_deposits: LookupMap::new(StorageKey::StorageDeposits),
             bid_history_length: bid_history_length.unwrap_or(BID_HISTORY_LENGTH_DEFAULT),
+            rentals: UnorderedMap::new(StorageKey::Rentals),
+            rentals_by_owner_id: LookupMap::new(StorageKey::RentalsByOwnerId),
+            paused: vec![],
+            pausers: UnorderedSet::new(StorageKey::Pausers),
         };
         // support NEAR by default
         this.ft_token_ids.insert(&"near".to_string());
@@ -98,6 +126,7 @@ impl Contract {
                 this.ft_token_ids.insert(ft_token_id.as_ref());
             }
         }
+        write_state_version();
 
         this
     }
@@ -135,8 +164,7 @@ impl Contract {
         assert_one_yocto();
         let owner_id = env::predecessor_account_id();
         let mut amount = self.storage_deposits.remove(&owner_id).unwrap_or(0);
-        let sales = self.by_owner_id.get(&owner_id);
-        let len = sales.map(|s| s.len()).unwrap_or_default();
+        let len = self.internal_supply_listings_by_owner_id(&owner_id);
         let diff = u128::from(len) * STORAGE_PER_SALE;
         amount -= diff;
         if amount > 0 {
@@ -178,3 +206,120 @@ impl Contract {
         U128(STORAGE_PER_SALE)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::MockedBlockchain;
+    use near_sdk::{testing_env, VMContext};
+
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn owner() -> ValidAccountId {
+        ValidAccountId::try_from("owner.near").unwrap()
+    }
+    fn alice() -> ValidAccountId {
+        ValidAccountId::try_from("alice.near").unwrap()
+    }
+    fn nft() -> ValidAccountId {
+        ValidAccountId::try_from("nft.near").unwrap()
+    }
+
+    fn get_context(predecessor_account_id: AccountId, attached_deposit: Balance) -> VMContext {
+        VMContext {
+            current_account_id: "market.near".to_string(),
+            signer_account_id: predecessor_account_id.clone(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id,
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 0,
+            account_balance: 1000 * 10u128.pow(24),
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn new_contract() -> Contract {
+        testing_env!(get_context(owner().into(), 0));
+        Contract::new(owner(), None, None)
+    }
+
+    #[test]
+    fn pausers_pause_and_unpause() {
+        let mut contract = new_contract();
+        contract.add_pauser(alice());
+        testing_env!(get_context(alice().into(), 0));
+        contract.pause(vec![PauseFlag::Purchases, PauseFlag::Listings]);
+        assert!(contract.is_paused(PauseFlag::Purchases));
+        assert!(contract.is_paused(PauseFlag::Listings));
+        contract.unpause(vec![PauseFlag::Listings]);
+        assert_eq!(contract.get_paused(), vec![PauseFlag::Purchases]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Pauser's method")]
+    fn only_pausers_pause() {
+        let mut contract = new_contract();
+        testing_env!(get_context(alice().into(), 0));
+        contract.pause(vec![PauseFlag::Purchases]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Purchases is paused")]
+    fn paused_purchases_reject_offers() {
+        let mut contract = new_contract();
+        contract.pause(vec![PauseFlag::Purchases]);
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.offer(nft(), "1".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Purchases is paused")]
+    fn paused_purchases_reject_rents() {
+        let mut contract = new_contract();
+        contract.pause(vec![PauseFlag::Purchases]);
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.rent(nft(), "1".to_string(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Listings is paused")]
+    fn paused_listings_reject_price_updates() {
+        let mut contract = new_contract();
+        contract.pause(vec![PauseFlag::Listings]);
+        testing_env!(get_context(alice().into(), 1));
+        contract.update_price(nft(), "1".to_string(), ValidAccountId::try_from("near").unwrap(), U128(1));
+    }
+
+    fn sale_with_bids(bid_tokens: usize) -> Sale {
+        let mut bids = Bids::new();
+        for i in 0..bid_tokens {
+            bids.insert(format!("ft{}.near", i), vec![Bid { owner_id: alice().into(), price: U128(1) }]);
+        }
+        Sale {
+            owner_id: alice().into(),
+            approval_id: 0,
+            nft_contract_id: nft().into(),
+            token_id: "1".to_string(),
+            sale_conditions: SaleConditions::new(),
+            bids,
+            created_at: U64(0),
+            is_auction: false,
+            token_type: None,
+        }
+    }
+
+    #[test]
+    fn max_len_payout_leaves_room_for_refunds() {
+        assert_eq!(sale_with_bids(0).max_len_payout(), MAX_PAYOUTS_AND_REFUNDS);
+        assert_eq!(sale_with_bids(3).max_len_payout(), MAX_PAYOUTS_AND_REFUNDS - 3);
+        assert_eq!(sale_with_bids(MAX_PAYOUTS_AND_REFUNDS + 1).max_len_payout(), 1);
+    }
+}
@@ -19,6 +19,8 @@ trait NonFungibleTokenApprovalsReceiver {
         approval_id: u64,
         msg: String,
     );
+
+    fn nft_on_revoke(&mut self, token_id: TokenId);
 }
 
 #[near_bindgen]
@@ -32,6 +34,8 @@ impl NonFungibleTokenApprovalsReceiver for Contract {
         approval_id: u64,
         msg: String,
     ) {
+        self.assert_not_paused(PauseFlag::Listings);
+
         // enforce cross contract call and owner_id is signer
 
         let nft_contract_id = env::predecessor_account_id();
@@ -51,13 +55,28 @@ impl NonFungibleTokenApprovalsReceiver for Contract {
 
         let storage_amount = self.storage_amount().0;
         let owner_paid_storage = self.storage_deposits.get(&signer_id).unwrap_or(0);
-        let signer_storage_required = (self.get_supply_by_owner_id(signer_id).0 + 1) as u128 * storage_amount;
+        let signer_storage_required = (self.internal_supply_listings_by_owner_id(&signer_id) + 1) as u128 * storage_amount;
         assert!(
             owner_paid_storage >= signer_storage_required,
             "Insufficient storage paid: {}, for {} sales at {} rate of per sale",
             owner_paid_storage, signer_storage_required / STORAGE_PER_SALE, STORAGE_PER_SALE
         );
 
+        // CUSTOM - rental listing
+        if let Ok(RentalArgs { price_per_period, period, max_periods }) = near_sdk::serde_json::from_str(&msg) {
+            self.internal_add_rental(Rental {
+                owner_id: owner_id.into(),
+                approval_id,
+                nft_contract_id,
+                token_id,
+                price_per_period,
+                period,
+                max_periods: max_periods.unwrap_or(1),
+                created_at: U64(env::block_timestamp()/1000000),
+            });
+            return;
+        }
+
         let SaleArgs { sale_conditions, token_type, is_auction } =
             near_sdk::serde_json::from_str(&msg).expect("Not valid SaleArgs");
 
@@ -73,8 +92,10 @@ impl NonFungibleTokenApprovalsReceiver for Contract {
         // env::log(format!("add_sale for owner: {}", &owner_id).as_bytes());
 
         let bids = HashMap::new();
+        let sale_conditions_log = sale_conditions.clone();
 
         let contract_and_token_id = format!("{}{}{}", nft_contract_id, DELIMETER, token_id);
+        assert!(self.rentals.get(&contract_and_token_id).is_none(), "Token is listed for rent");
         self.sales.insert(
             &contract_and_token_id,
             &Sale {
@@ -89,6 +110,14 @@ impl NonFungibleTokenApprovalsReceiver for Contract {
                 is_auction: is_auction.unwrap_or(false),
             },
         );
+        EventLog::new(EventLogVariant::SaleListed(vec![SaleListedLog {
+            owner_id: owner_id.clone().into(),
+            nft_contract_id: nft_contract_id.clone(),
+            token_id: token_id.clone(),
+            token_type: token_type.clone(),
+            sale_conditions: sale_conditions_log,
+            is_auction: is_auction.unwrap_or(false),
+        }])).emit();
 
         // extra for views
 
@@ -145,4 +174,30 @@ impl NonFungibleTokenApprovalsReceiver for Contract {
                 .insert(&token_type, &by_nft_token_type);
         }
     }
+
+    /// the NFT contract revoked our approval, the sale can't complete anymore so drop it
+
+    fn nft_on_revoke(&mut self, token_id: TokenId) {
+        let nft_contract_id = env::predecessor_account_id();
+        let contract_and_token_id = format!("{}{}{}", nft_contract_id, DELIMETER, token_id);
+        if self.rentals.get(&contract_and_token_id).is_some() {
+            let rental = self.internal_remove_rental(nft_contract_id, token_id);
+            EventLog::new(EventLogVariant::RentalRemoved(vec![SaleRemovedLog {
+                owner_id: rental.owner_id,
+                nft_contract_id: rental.nft_contract_id,
+                token_id: rental.token_id,
+            }])).emit();
+            return;
+        }
+        if self.sales.get(&contract_and_token_id).is_none() {
+            return;
+        }
+        let sale = self.internal_remove_sale(nft_contract_id, token_id);
+        self.refund_all_bids(&sale);
+        EventLog::new(EventLogVariant::SaleRemoved(vec![SaleRemovedLog {
+            owner_id: sale.owner_id,
+            nft_contract_id: sale.nft_contract_id,
+            token_id: sale.token_id,
+        }])).emit();
+    }
 }
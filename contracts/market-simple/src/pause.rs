@@ -0,0 +1,73 @@
+use crate::*;
+
+/// CUSTOM - functionality that can be paused in an emergency
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PauseFlag {
+    /// new sales and rentals, and price updates
+    Listings,
+    /// offers, accepting offers, FT purchases and rents
+    Purchases,
+}
+
+/// CUSTOM - the owner and pausers stop listings or purchases until unpaused
+/// removing sales and callbacks of purchases already in flight aren't paused so they can settle or refund
+#[near_bindgen]
+impl Contract {
+    /// only owner
+
+    pub fn add_pauser(&mut self, account_id: ValidAccountId) -> bool {
+        self.assert_owner();
+        self.pausers.insert(account_id.as_ref())
+    }
+
+    pub fn remove_pauser(&mut self, account_id: ValidAccountId) -> bool {
+        self.assert_owner();
+        self.pausers.remove(account_id.as_ref())
+    }
+
+    /// only pauser
+
+    pub fn pause(&mut self, flags: Vec<PauseFlag>) {
+        self.assert_pauser();
+        for flag in flags {
+            if !self.paused.contains(&flag) {
+                self.paused.push(flag);
+            }
+        }
+    }
+
+    pub fn unpause(&mut self, flags: Vec<PauseFlag>) {
+        self.assert_pauser();
+        self.paused.retain(|flag| !flags.contains(flag));
+    }
+
+    /// views
+
+    pub fn get_pausers(&self) -> Vec<AccountId> {
+        self.pausers.to_vec()
+    }
+
+    pub fn get_paused(&self) -> Vec<PauseFlag> {
+        self.paused.clone()
+    }
+
+    pub fn is_paused(&self, flag: PauseFlag) -> bool {
+        self.paused.contains(&flag)
+    }
+}
+
+impl Contract {
+    /// the owner is always a pauser
+    pub(crate) fn assert_pauser(&self) {
+        let predecessor_account_id = env::predecessor_account_id();
+        assert!(
+            predecessor_account_id == self.owner_id || self.pausers.contains(&predecessor_account_id),
+            "Pauser's method"
+        );
+    }
+
+    pub(crate) fn assert_not_paused(&self, flag: PauseFlag) {
+        assert!(!self.paused.contains(&flag), "{:?} is paused", flag);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::*;
+
+/// CUSTOM - rental listing, renters pay price_per_period in NEAR for each period (ms) they rent
+/// the NFT contract sets them as the user of the token and returns the royalty payout of the rent
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Rental {
+    pub owner_id: AccountId,
+    pub approval_id: u64,
+    pub nft_contract_id: AccountId,
+    pub token_id: TokenId,
+    pub price_per_period: U128,
+    pub period: U64,
+    pub max_periods: u64,
+    pub created_at: U64,
+}
+
+/// msg of nft_approve to list a token for rent instead of sale
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RentalArgs {
+    pub price_per_period: U128,
+    pub period: U64,
+    pub max_periods: Option<u64>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// for add rental see: nft_callbacks.rs
+
+    #[payable]
+    pub fn remove_rental(&mut self, nft_contract_id: ValidAccountId, token_id: String) {
+        assert_one_yocto();
+        let rental = self.internal_remove_rental(nft_contract_id.into(), token_id);
+        assert_eq!(env::predecessor_account_id(), rental.owner_id, "Must be rental owner");
+        EventLog::new(EventLogVariant::RentalRemoved(vec![SaleRemovedLog {
+            owner_id: rental.owner_id,
+            nft_contract_id: rental.nft_contract_id,
+            token_id: rental.token_id,
+        }])).emit();
+    }
+
+    /// attach price_per_period * periods plus STORAGE_FOR_RENTAL_USER, the excess is refunded
+    #[payable]
+    pub fn rent(&mut self, nft_contract_id: ValidAccountId, token_id: String, periods: u64) -> Promise {
+        self.assert_not_paused(PauseFlag::Purchases);
+        let contract_id: AccountId = nft_contract_id.into();
+        let contract_and_token_id = format!("{}{}{}", contract_id, DELIMETER, token_id);
+        let rental = self.rentals.get(&contract_and_token_id).expect("No rental");
+        let renter_id = env::predecessor_account_id();
+        assert_ne!(rental.owner_id, renter_id, "Cannot rent your own token.");
+        assert!(periods > 0 && periods <= rental.max_periods, "Can rent 1 to {} periods", rental.max_periods);
+
+        let price = rental.price_per_period.0 * u128::from(periods);
+        let required_deposit = price + STORAGE_FOR_RENTAL_USER;
+        let deposit = env::attached_deposit();
+        assert!(deposit >= required_deposit, "Must attach at least {} yoctoNEAR", required_deposit);
+        if deposit > required_deposit {
+            Promise::new(renter_id.clone()).transfer(deposit - required_deposit);
+        }
+        let expires = env::block_timestamp() / 1_000_000 + rental.period.0 * periods;
+
+        ext_nft_payout::nft_set_user_payout(
+            token_id,
+            renter_id.clone(),
+            U64(expires),
+            rental.approval_id,
+            U128(price),
+            MAX_PAYOUTS_AND_REFUNDS as u32,
+            &contract_id,
+            STORAGE_FOR_RENTAL_USER,
+            GAS_FOR_NFT_TRANSFER,
+        )
+        .then(ext_self_rental::resolve_rent(
+            renter_id,
+            rental,
+            U128(price),
+            U64(expires),
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_ROYALTIES,
+        ))
+    }
+
+    /// self callback
+
+    #[private]
+    pub fn resolve_rent(&mut self, renter_id: AccountId, rental: Rental, price: U128, expires: U64) -> bool {
+        let payout = if let Some(payout) = payout_from_result(price.0, &rental.owner_id, MAX_PAYOUTS_AND_REFUNDS) {
+            payout
+        } else {
+            // the attached storage deposit was refunded to the market by the failed call
+            Promise::new(renter_id).transfer(price.0 + STORAGE_FOR_RENTAL_USER);
+            return false;
+        };
+        EventLog::new(EventLogVariant::TokenRented(vec![TokenRentedLog {
+            owner_id: rental.owner_id,
+            renter_id,
+            nft_contract_id: rental.nft_contract_id,
+            token_id: rental.token_id,
+            price,
+            expires,
+        }])).emit();
+        for (receiver_id, amount) in payout {
+            Promise::new(receiver_id).transfer(amount.0);
+        }
+        true
+    }
+
+    /// views
+
+    pub fn get_supply_rentals(&self) -> U64 {
+        U64(self.rentals.len())
+    }
+
+    pub fn get_rental(&self, nft_contract_token: ContractAndTokenId) -> Option<Rental> {
+        self.rentals.get(&nft_contract_token)
+    }
+
+    pub fn get_rentals_by_owner_id(
+        &self,
+        account_id: AccountId,
+        from_index: U64,
+        limit: u64,
+    ) -> Vec<Rental> {
+        let rentals = if let Some(rentals_by_owner_id) = self.rentals_by_owner_id.get(&account_id) {
+            rentals_by_owner_id
+        } else {
+            return vec![];
+        };
+        rentals.as_vector().iter()
+            .skip(u64::from(from_index) as usize)
+            .take(limit as usize)
+            .map(|contract_and_token_id| self.rentals.get(&contract_and_token_id).unwrap())
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_add_rental(&mut self, rental: Rental) {
+        let contract_and_token_id = format!("{}{}{}", rental.nft_contract_id, DELIMETER, rental.token_id);
+        assert!(self.sales.get(&contract_and_token_id).is_none(), "Token is listed for sale");
+        assert!(rental.price_per_period.0 > 0 && rental.period.0 > 0, "Rental price and period must be positive");
+
+        let mut rentals_by_owner_id = self.rentals_by_owner_id.get(&rental.owner_id).unwrap_or_else(|| {
+            UnorderedSet::new(
+                StorageKey::RentalsByOwnerIdInner {
+                    account_id_hash: hash_account_id(&rental.owner_id),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+        });
+        rentals_by_owner_id.insert(&contract_and_token_id);
+        self.rentals_by_owner_id.insert(&rental.owner_id, &rentals_by_owner_id);
+
+        EventLog::new(EventLogVariant::RentalListed(vec![RentalListedLog {
+            owner_id: rental.owner_id.clone(),
+            nft_contract_id: rental.nft_contract_id.clone(),
+            token_id: rental.token_id.clone(),
+            price_per_period: rental.price_per_period,
+            period: rental.period,
+            max_periods: rental.max_periods,
+        }])).emit();
+        self.rentals.insert(&contract_and_token_id, &rental);
+    }
+
+    pub(crate) fn internal_remove_rental(&mut self, nft_contract_id: AccountId, token_id: TokenId) -> Rental {
+        let contract_and_token_id = format!("{}{}{}", &nft_contract_id, DELIMETER, token_id);
+        let rental = self.rentals.remove(&contract_and_token_id).expect("No rental");
+
+        let mut rentals_by_owner_id = self.rentals_by_owner_id.get(&rental.owner_id).expect("No rental by_owner_id");
+        rentals_by_owner_id.remove(&contract_and_token_id);
+        if rentals_by_owner_id.is_empty() {
+            self.rentals_by_owner_id.remove(&rental.owner_id);
+        } else {
+            self.rentals_by_owner_id.insert(&rental.owner_id, &rentals_by_owner_id);
+        }
+
+        rental
+    }
+}
+
+/// self call
+
+#[ext_contract(ext_self_rental)]
+trait ExtSelfRental {
+    fn resolve_rent(&mut self, renter_id: AccountId, rental: Rental, price: U128, expires: U64) -> Promise;
+}
@@ -1,5 +1,4 @@
 use crate::*;
-use near_sdk::promise_result_as_success;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
@@ -22,6 +21,14 @@ pub struct Sale {
     pub token_type: Option<String>,
 }
 
+impl Sale {
+    /// payout entries the NFT contract may return, the rest of MAX_PAYOUTS_AND_REFUNDS refunds bids
+    /// NFT contract merges royalties to fit, at least the seller is always paid
+    pub(crate) fn max_len_payout(&self) -> usize {
+        MAX_PAYOUTS_AND_REFUNDS.saturating_sub(self.bids.len()).max(1)
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct PurchaseArgs {
@@ -40,7 +47,12 @@ impl Contract {
         let sale = self.internal_remove_sale(nft_contract_id.into(), token_id);
         let owner_id = env::predecessor_account_id();
         assert_eq!(owner_id, sale.owner_id, "Must be sale owner");
-        self.refund_all_bids(&sale.bids);
+        self.refund_all_bids(&sale);
+        EventLog::new(EventLogVariant::SaleRemoved(vec![SaleRemovedLog {
+            owner_id: sale.owner_id,
+            nft_contract_id: sale.nft_contract_id,
+            token_id: sale.token_id,
+        }])).emit();
     }
 
     #[payable]
@@ -52,6 +64,7 @@ impl Contract {
         price: U128,
     ) {
         assert_one_yocto();
+        self.assert_not_paused(PauseFlag::Listings);
         let contract_id: AccountId = nft_contract_id.into();
         let contract_and_token_id = format!("{}{}{}", contract_id, DELIMETER, token_id);
         let mut sale = self.sales.get(&contract_and_token_id).expect("No sale");
@@ -65,10 +78,17 @@ impl Contract {
         }
         sale.sale_conditions.insert(ft_token_id.into(), price);
         self.sales.insert(&contract_and_token_id, &sale);
+        EventLog::new(EventLogVariant::SaleUpdated(vec![SaleUpdatedLog {
+            owner_id: sale.owner_id,
+            nft_contract_id: sale.nft_contract_id,
+            token_id: sale.token_id,
+            sale_conditions: sale.sale_conditions,
+        }])).emit();
     }
 
     #[payable]
     pub fn offer(&mut self, nft_contract_id: ValidAccountId, token_id: String) {
+        self.assert_not_paused(PauseFlag::Purchases);
         let contract_id: AccountId = nft_contract_id.into();
         let contract_and_token_id = format!("{}{}{}", contract_id, DELIMETER, token_id);
         let mut sale = self.sales.get(&contract_and_token_id).expect("No sale");
@@ -142,14 +162,28 @@ impl Contract {
                     GAS_FOR_FT_TRANSFER,
                 );
             }
+            EventLog::new(EventLogVariant::BidRefunded(vec![BidLog {
+                bidder_id: current_bid.owner_id.clone(),
+                nft_contract_id: sale.nft_contract_id.clone(),
+                token_id: sale.token_id.clone(),
+                ft_token_id: ft_token_id.clone(),
+                price: current_bid.price,
+            }])).emit();
         }
         
+        EventLog::new(EventLogVariant::BidPlaced(vec![BidLog {
+            bidder_id: new_bid.owner_id.clone(),
+            nft_contract_id: sale.nft_contract_id.clone(),
+            token_id: sale.token_id.clone(),
+            ft_token_id,
+            price: new_bid.price,
+        }])).emit();
         bids_for_token_id.push(new_bid);
         if bids_for_token_id.len() > self.bid_history_length as usize {
             bids_for_token_id.remove(0);
         }
         
-        self.sales.insert(&contract_and_token_id, &sale);
+        self.sales.insert(&contract_and_token_id, sale);
     }
 
     pub fn accept_offer(
@@ -158,8 +192,9 @@ impl Contract {
         token_id: String,
         ft_token_id: ValidAccountId,
     ) {
+        self.assert_not_paused(PauseFlag::Purchases);
         let contract_id: AccountId = nft_contract_id.into();
-        let contract_and_token_id = format!("{}{}{}", contract_id.clone(), DELIMETER, token_id.clone());
+        let contract_and_token_id = format!("{}{}{}", contract_id, DELIMETER, token_id);
         // remove bid before proceeding to process purchase
         let mut sale = self.sales.get(&contract_and_token_id).expect("No sale");
         let bids_for_token_id = sale.bids.remove(ft_token_id.as_ref()).expect("No bids");
@@ -185,14 +220,15 @@ impl Contract {
         buyer_id: AccountId,
     ) -> Promise {
         let sale = self.internal_remove_sale(nft_contract_id.clone(), token_id.clone());
+        let max_len_payout = sale.max_len_payout();
 
-        ext_contract::nft_transfer_payout(
+        ext_nft_payout::nft_transfer_payout(
             buyer_id.clone(),
             token_id,
             sale.approval_id,
             "payout from market".to_string(),
             price,
-			10,
+            max_len_payout as u32,
             &nft_contract_id,
             1,
             GAS_FOR_NFT_TRANSFER,
@@ -220,29 +256,11 @@ impl Contract {
     ) -> U128 {
 
         // checking for payout information
-        let payout_option = promise_result_as_success().and_then(|value| {
-            // None means a bad payout from bad NFT contract
-            near_sdk::serde_json::from_slice::<Payout>(&value)
-                .ok()
-                .and_then(|payout| {
-                    // gas to do 10 FT transfers (and definitely 10 NEAR transfers)
-                    if payout.len() + sale.bids.len() > 10 || payout.is_empty() {
-                        env::log(format!("Cannot have more than 10 royalties and sale.bids refunds").as_bytes());
-                        None
-                    } else {
-                        // TODO off by 1 e.g. payouts are fractions of 3333 + 3333 + 3333
-                        let mut remainder = price.0;
-                        for &value in payout.values() {
-                            remainder = remainder.checked_sub(value.0)?;
-                        }
-                        if remainder == 0 || remainder == 1 {
-                            Some(payout)
-                        } else {
-                            None
-                        }
-                    }
-                })
-        });
+        let payout_option = payout_from_result(
+            price.0,
+            &sale.owner_id,
+            sale.max_len_payout(),
+        );
         // is payout option valid?
         let payout = if let Some(payout_option) = payout_option {
             payout_option
@@ -250,11 +268,24 @@ impl Contract {
             if ft_token_id == "near" {
                 Promise::new(buyer_id).transfer(u128::from(price));
             }
+            EventLog::new(EventLogVariant::SaleRemoved(vec![SaleRemovedLog {
+                owner_id: sale.owner_id,
+                nft_contract_id: sale.nft_contract_id,
+                token_id: sale.token_id,
+            }])).emit();
             // leave function and return all FTs in ft_resolve_transfer
             return price;
         };
         // Going to payout everyone, first return all outstanding bids (accepted offer bid was already removed)
-        self.refund_all_bids(&sale.bids);
+        self.refund_all_bids(&sale);
+        EventLog::new(EventLogVariant::SaleCompleted(vec![SaleCompletedLog {
+            owner_id: sale.owner_id,
+            buyer_id,
+            nft_contract_id: sale.nft_contract_id,
+            token_id: sale.token_id,
+            ft_token_id: ft_token_id.clone(),
+            price,
+        }])).emit();
 
         // NEAR payouts
         if ft_token_id == "near" {
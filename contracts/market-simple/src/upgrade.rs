@@ -0,0 +1,101 @@
+use crate::*;
+
+/// gas kept by upgrade, the rest is attached to migrate
+const GAS_FOR_UPGRADE: Gas = 20_000_000_000_000;
+
+/// bump when a deployed Contract layout changes and add that layout to VersionedContract
+/// layouts that were never deployed don't need a version, V1 converts straight to the current layout
+pub const STATE_VERSION: u8 = 2;
+/// state version is stored outside of the Contract so the layout can be known before reading it
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// Contract layout deployed before state versioning
+#[derive(BorshDeserialize)]
+pub struct ContractV1 {
+    pub owner_id: AccountId,
+    pub sales: UnorderedMap<ContractAndTokenId, Sale>,
+    pub by_owner_id: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    pub by_nft_contract_id: LookupMap<AccountId, UnorderedSet<TokenId>>,
+    pub by_nft_token_type: LookupMap<AccountId, UnorderedSet<ContractAndTokenId>>,
+    pub ft_token_ids: UnorderedSet<AccountId>,
+    pub storage_deposits: LookupMap<AccountId, Balance>,
+    pub bid_history_length: u8,
+}
+
+impl From<ContractV1> for Contract {
+    fn from(old: ContractV1) -> Self {
+        Contract {
+            owner_id: old.owner_id,
+            sales: old.sales,
+            by_owner_id: old.by_owner_id,
+            by_nft_contract_id: old.by_nft_contract_id,
+            by_nft_token_type: old.by_nft_token_type,
+            ft_token_ids: old.ft_token_ids,
+            storage_deposits: old.storage_deposits,
+            bid_history_length: old.bid_history_length,
+            rentals: UnorderedMap::new(StorageKey::Rentals),
+            rentals_by_owner_id: LookupMap::new(StorageKey::RentalsByOwnerId),
+            paused: vec![],
+            pausers: UnorderedSet::new(StorageKey::Pausers),
+        }
+    }
+}
+
+/// every layout of Contract that may be in state
+pub enum VersionedContract {
+    V1(ContractV1),
+    V2(Contract),
+}
+
+impl VersionedContract {
+    pub fn read() -> Self {
+        let state_version = env::storage_read(STATE_VERSION_KEY).map(|v| v[0]).unwrap_or(1);
+        match state_version {
+            1 => VersionedContract::V1(env::state_read().expect("Failed to read V1 state")),
+            2 => VersionedContract::V2(env::state_read().expect("Failed to read V2 state")),
+            _ => env::panic(format!("Unknown state version {}", state_version).as_bytes()),
+        }
+    }
+
+    pub fn into_current(self) -> Contract {
+        match self {
+            VersionedContract::V1(old) => old.into(),
+            VersionedContract::V2(contract) => contract,
+        }
+    }
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &[STATE_VERSION]);
+}
+
+#[near_bindgen]
+impl Contract {
+    /// only owner - deploys new code and migrates state in the same receipt
+    /// the input of this call is the raw wasm of the new contract, not JSON
+    pub fn upgrade(&self) -> Promise {
+        self.assert_owner();
+        let code = env::input().expect("No code to deploy");
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                b"migrate".to_vec(),
+                vec![],
+                NO_DEPOSIT,
+                env::prepaid_gas() - env::used_gas() - GAS_FOR_UPGRADE,
+            )
+    }
+
+    /// called by upgrade with the new code, reads any previous layout and writes the current one
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate() -> Self {
+        let contract = VersionedContract::read().into_current();
+        write_state_version();
+        contract
+    }
+
+    pub fn version(&self) -> String {
+        format!("{}-state.{}", env!("CARGO_PKG_VERSION"), STATE_VERSION)
+    }
+}
@@ -0,0 +1,88 @@
+/*!
+* NEP-297 event logs shared by the NFT, market and FT contracts.
+*
+* Each contract declares its events as an enum and implements EventStandard for it.
+* This crate doesn't depend on near-sdk, the contracts use different versions of it,
+* so EventStandard::log writes to the receipt logs with the contract's own env.
+*/
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// standard, version and logger of a contract's events
+pub trait EventStandard: Serialize {
+    /// name of standard e.g. nep171
+    const STANDARD: &'static str;
+    /// e.g. 1.0.0
+    const VERSION: &'static str;
+
+    /// writes a message to the receipt logs
+    fn log(message: &str);
+}
+
+/// Interface to capture data about an event
+///
+/// Arguments:
+/// * `standard`: name of standard e.g. nep171
+/// * `version`: e.g. 1.0.0
+/// * `event`: associate event data
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventLog<T> {
+    pub standard: String,
+    pub version: String,
+
+    // `flatten` to not have "event": {<EventLogVariant>} in the JSON, just have the contents of {<EventLogVariant>}.
+    #[serde(flatten)]
+    pub event: T,
+}
+
+impl<T: Serialize> fmt::Display for EventLog<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "EVENT_JSON:{}",
+            &serde_json::to_string(self).map_err(|_| fmt::Error)?
+        ))
+    }
+}
+
+impl<T: EventStandard> EventLog<T> {
+    pub fn new(event: T) -> Self {
+        Self {
+            standard: T::STANDARD.to_string(),
+            version: T::VERSION.to_string(),
+            event,
+        }
+    }
+
+    /// writes the event to the receipt logs
+    pub fn emit(&self) {
+        T::log(&self.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(tag = "event", content = "data")]
+    #[serde(rename_all = "snake_case")]
+    enum TestEvent {
+        TestMint(Vec<String>),
+    }
+
+    impl EventStandard for TestEvent {
+        const STANDARD: &'static str = "test";
+        const VERSION: &'static str = "1.0.0";
+
+        fn log(_message: &str) {}
+    }
+
+    #[test]
+    fn event_json_flattens_the_event() {
+        let event = EventLog::new(TestEvent::TestMint(vec!["1".to_string()]));
+        assert_eq!(
+            event.to_string(),
+            r#"EVENT_JSON:{"standard":"test","version":"1.0.0","event":"test_mint","data":["1"]}"#
+        );
+    }
+}
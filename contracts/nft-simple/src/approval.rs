@@ -0,0 +1,117 @@
+use crate::*;
+
+static DELIMETER: &str = "||";
+
+/// CUSTOM - optional conditions of an approval, set with nft_approve
+/// conditions belong to one approval_id and don't apply to a later approval of the same account
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ApprovalConditions {
+    pub approval_id: u64,
+    /// ms timestamp, the approved account can't transfer from then on
+    pub expires_at: Option<U64>,
+    /// the approved account can only transfer with nft_transfer_payout for at least this balance
+    pub min_price: Option<U128>,
+}
+
+impl ApprovalConditions {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at.0 <= env::block_timestamp() / 1_000_000)
+            .unwrap_or(false)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// views
+
+    /// conditions of the current approval of account_id, None if unconditional or not approved
+    pub fn nft_approval_conditions(&self, token_id: TokenId, account_id: AccountId) -> Option<ApprovalConditions> {
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        self.internal_approval_conditions(&token_id, &token, &account_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_approval_conditions(
+        &self,
+        token_id: &TokenId,
+        token: &Token,
+        account_id: &AccountId,
+    ) -> Option<ApprovalConditions> {
+        let approval_id = token.approved_account_ids.get(account_id)?;
+        self.approval_conditions.get(&approval_key(token_id, account_id))
+            .filter(|conditions| &conditions.approval_id == approval_id)
+    }
+
+    /// stores the conditions of a new approval, removes them if there are none
+    pub(crate) fn internal_set_approval_conditions(
+        &mut self,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        approval_id: u64,
+        expires_at: Option<U64>,
+        min_price: Option<U128>,
+    ) {
+        let key = approval_key(token_id, account_id);
+        if expires_at.is_none() && min_price.is_none() {
+            self.approval_conditions.remove(&key);
+            return;
+        }
+        if let Some(expires_at) = expires_at {
+            assert!(expires_at.0 > env::block_timestamp() / 1_000_000, "expires_at must be in the future");
+        }
+        self.approval_conditions.insert(&key, &ApprovalConditions { approval_id, expires_at, min_price });
+    }
+
+    pub(crate) fn assert_approval_not_expired(&self, token_id: &TokenId, token: &Token, account_id: &AccountId) {
+        if let Some(conditions) = self.internal_approval_conditions(token_id, token, account_id) {
+            assert!(!conditions.is_expired(), "Approval expired");
+        }
+    }
+
+    /// balance is the price of nft_transfer_payout, None for transfers without a price
+    pub(crate) fn assert_approval_conditions(
+        &self,
+        token_id: &TokenId,
+        token: &Token,
+        account_id: &AccountId,
+        balance: Option<Balance>,
+    ) {
+        if let Some(conditions) = self.internal_approval_conditions(token_id, token, account_id) {
+            assert!(!conditions.is_expired(), "Approval expired");
+            if let Some(min_price) = conditions.min_price {
+                let balance = balance.expect("Approval requires a sale with nft_transfer_payout");
+                assert!(balance >= min_price.0, "Price is below the owner's minimum of {}", min_price.0);
+            }
+        }
+    }
+
+    /// call where approvals are refunded, refunds the storage of their conditions to owner_id
+    pub(crate) fn internal_remove_approval_conditions<'a, I>(&mut self, token_id: &TokenId, owner_id: &AccountId, account_ids: I)
+    where
+        I: Iterator<Item = &'a AccountId>,
+    {
+        let storage_released = self.internal_release_approval_conditions(token_id, account_ids);
+        if storage_released > 0 {
+            Promise::new(owner_id.clone()).transfer(Balance::from(storage_released) * env::storage_byte_cost());
+        }
+    }
+
+    /// removes the conditions and returns the bytes released, the caller refunds them
+    pub(crate) fn internal_release_approval_conditions<'a, I>(&mut self, token_id: &TokenId, account_ids: I) -> StorageUsage
+    where
+        I: Iterator<Item = &'a AccountId>,
+    {
+        let initial_storage_usage = env::storage_usage();
+        for account_id in account_ids {
+            self.approval_conditions.remove(&approval_key(token_id, account_id));
+        }
+        initial_storage_usage - env::storage_usage()
+    }
+}
+
+pub(crate) fn approval_key(token_id: &TokenId, account_id: &AccountId) -> String {
+    format!("{}{}{}", token_id, DELIMETER, account_id)
+}
@@ -0,0 +1,252 @@
+use crate::*;
+
+const MAX_ATTRIBUTES: usize = 20;
+static DELIMETER: &str = "||";
+/// rarity scores are scaled by this to stay integers
+pub const RARITY_SCORE_SCALE: u64 = 10_000;
+
+/// CUSTOM - typed attribute of a token or series, e.g. { "trait_type": "background", "value": "gold" }
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Attribute {
+    pub trait_type: String,
+    pub value: String,
+}
+
+/// CUSTOM - number of tokens of a token_type or series with a trait_type and value
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TraitCount {
+    pub trait_type: String,
+    pub value: String,
+    pub count: U64,
+}
+
+/// CUSTOM - tokens are indexed by (token_type, trait_type, value)
+/// editions of a series have no token_type and are indexed under their series_id
+/// editions use the attributes of their series unless they have their own
+/// tokens without a token_type or series have no group and can't have attributes
+#[near_bindgen]
+impl Contract {
+    /// minters set the attributes of a token they can mint, attach a deposit to cover storage
+    #[payable]
+    pub fn nft_set_attributes(&mut self, token_id: TokenId, attributes: Vec<Attribute>) {
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        self.assert_minter(&token.token_type);
+        assert!(
+            self.internal_attribute_group(&token_id).is_some(),
+            "Only tokens with a token_type or series can have attributes"
+        );
+        assert_valid_attributes(&attributes);
+        let initial_storage_usage = env::storage_usage();
+
+        self.internal_unindex_attributes(&token_id);
+        self.token_attributes.insert(&token_id, &attributes);
+        self.internal_index_attributes(&token_id);
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_charge_storage(storage_used);
+    }
+
+    /// only the creator, before any edition is minted
+    #[payable]
+    pub fn set_series_attributes(&mut self, series_id: SeriesId, attributes: Vec<Attribute>) {
+        let series = self.internal_series(&series_id);
+        assert_eq!(env::predecessor_account_id(), series.creator_id, "Only the creator can set attributes");
+        assert_eq!(series.minted, 0, "Series attributes are fixed once editions are minted");
+        assert_valid_attributes(&attributes);
+        let initial_storage_usage = env::storage_usage();
+
+        self.series_attributes.insert(&series_id, &attributes);
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_charge_storage(storage_used);
+    }
+
+    /// views
+
+    pub fn nft_attributes(&self, token_id: TokenId) -> Vec<Attribute> {
+        self.internal_attributes(&token_id)
+    }
+
+    pub fn get_series_attributes(&self, series_id: SeriesId) -> Vec<Attribute> {
+        self.series_attributes.get(&series_id).unwrap_or_default()
+    }
+
+    pub fn nft_supply_for_trait(&self, token_type: String, trait_type: String, value: String) -> U64 {
+        U64(self.tokens_by_trait.get(&trait_key(&token_type, &trait_type, &value)).map(|tokens| tokens.len()).unwrap_or(0))
+    }
+
+    pub fn nft_tokens_for_trait(
+        &self,
+        token_type: String,
+        trait_type: String,
+        value: String,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<JsonToken> {
+        let tokens = if let Some(tokens) = self.tokens_by_trait.get(&trait_key(&token_type, &trait_type, &value)) {
+            tokens
+        } else {
+            return vec![];
+        };
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        tokens.as_vector().iter()
+            .skip(start as usize)
+            .take(min(limit.unwrap_or(DEFAULT_PAGE_LIMIT), MAX_PAGE_LIMIT) as usize)
+            .map(|token_id| self.nft_token(token_id).unwrap())
+            .collect()
+    }
+
+    /// token_type is a token_type, or a series_id for editions
+    pub fn get_trait_counts(&self, token_type: String, from_index: Option<U64>, limit: Option<u64>) -> Vec<TraitCount> {
+        let traits = if let Some(traits) = self.traits_by_group.get(&token_type) {
+            traits
+        } else {
+            return vec![];
+        };
+        let start = u64::from(from_index.unwrap_or(U64(0)));
+        traits.as_vector().iter()
+            .skip(start as usize)
+            .take(page_limit(limit) as usize)
+            .map(|trait_value| {
+                let (trait_type, value) = trait_value.split_once(DELIMETER).unwrap();
+                TraitCount {
+                    count: U64(self.internal_trait_count(&token_type, trait_type, value)),
+                    trait_type: trait_type.to_string(),
+                    value: value.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    /// sum over the token's attributes of supply / tokens with that value, scaled by RARITY_SCORE_SCALE
+    /// higher is rarer
+    pub fn nft_rarity_score(&self, token_id: TokenId) -> U64 {
+        let group = if let Some(group) = self.internal_attribute_group(&token_id) {
+            group
+        } else {
+            return U64(0);
+        };
+        let supply = self.internal_attribute_group_supply(&group);
+        U64(self.internal_attributes(&token_id).iter()
+            .map(|attribute| self.internal_trait_count(&group, &attribute.trait_type, &attribute.value))
+            .filter(|count| *count > 0)
+            .map(|count| supply * RARITY_SCORE_SCALE / count)
+            .sum())
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_attributes(&self, token_id: &TokenId) -> Vec<Attribute> {
+        self.token_attributes.get(token_id)
+            .or_else(|| self.series_by_token.get(token_id).and_then(|series_id| self.series_attributes.get(&series_id)))
+            .unwrap_or_default()
+    }
+
+    /// token_type of the token, or series_id for editions
+    fn internal_attribute_group(&self, token_id: &TokenId) -> Option<String> {
+        let token = self.tokens_by_id.get(token_id).expect("No token");
+        token.token_type.or_else(|| self.series_by_token.get(token_id))
+    }
+
+    fn internal_trait_count(&self, group: &str, trait_type: &str, value: &str) -> u64 {
+        self.tokens_by_trait.get(&trait_key(group, trait_type, value)).map(|tokens| tokens.len()).unwrap_or(0)
+    }
+
+    fn internal_attribute_group_supply(&self, group: &str) -> u64 {
+        if let Some(tokens_per_type) = self.tokens_per_type.get(&group.to_string()) {
+            tokens_per_type.len()
+        } else {
+            self.series.get(&group.to_string()).map(|series| series.minted - series.burned).unwrap_or(0)
+        }
+    }
+
+    /// adds the token's current attributes to the trait index
+    pub(crate) fn internal_index_attributes(&mut self, token_id: &TokenId) {
+        let attributes = self.internal_attributes(token_id);
+        let group = match self.internal_attribute_group(token_id) {
+            Some(group) if !attributes.is_empty() => group,
+            _ => return,
+        };
+        let mut traits = self.traits_by_group.get(&group).unwrap_or_else(|| {
+            UnorderedSet::new(
+                StorageKey::TraitsByGroupInner {
+                    group_hash: hash_account_id(&group),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+        });
+        for Attribute { trait_type, value } in attributes {
+            let key = trait_key(&group, &trait_type, &value);
+            let mut tokens = self.tokens_by_trait.get(&key).unwrap_or_else(|| {
+                UnorderedSet::new(
+                    StorageKey::TokensByTraitInner {
+                        trait_hash: hash_account_id(&key),
+                    }
+                    .try_to_vec()
+                    .unwrap(),
+                )
+            });
+            tokens.insert(token_id);
+            self.tokens_by_trait.insert(&key, &tokens);
+            traits.insert(&trait_value_key(&trait_type, &value));
+        }
+        self.traits_by_group.insert(&group, &traits);
+    }
+
+    /// removes the token's current attributes from the trait index, call before they change
+    pub(crate) fn internal_unindex_attributes(&mut self, token_id: &TokenId) {
+        let attributes = self.internal_attributes(token_id);
+        let group = match self.internal_attribute_group(token_id) {
+            Some(group) if !attributes.is_empty() => group,
+            _ => return,
+        };
+        let mut traits = if let Some(traits) = self.traits_by_group.get(&group) {
+            traits
+        } else {
+            return;
+        };
+        for Attribute { trait_type, value } in attributes {
+            let key = trait_key(&group, &trait_type, &value);
+            if let Some(mut tokens) = self.tokens_by_trait.get(&key) {
+                tokens.remove(token_id);
+                if tokens.is_empty() {
+                    self.tokens_by_trait.remove(&key);
+                    traits.remove(&trait_value_key(&trait_type, &value));
+                } else {
+                    self.tokens_by_trait.insert(&key, &tokens);
+                }
+            }
+        }
+        if traits.is_empty() {
+            self.traits_by_group.remove(&group);
+        } else {
+            self.traits_by_group.insert(&group, &traits);
+        }
+    }
+}
+
+fn trait_key(group: &str, trait_type: &str, value: &str) -> String {
+    format!("{}{}{}", group, DELIMETER, trait_value_key(trait_type, value))
+}
+
+/// entry of traits_by_group
+fn trait_value_key(trait_type: &str, value: &str) -> String {
+    format!("{}{}{}", trait_type, DELIMETER, value)
+}
+
+fn assert_valid_attributes(attributes: &[Attribute]) {
+    assert!(attributes.len() <= MAX_ATTRIBUTES, "Cannot have more than {} attributes", MAX_ATTRIBUTES);
+    for (i, attribute) in attributes.iter().enumerate() {
+        assert!(
+            !attribute.trait_type.contains(DELIMETER) && !attribute.value.contains(DELIMETER),
+            "Attributes cannot contain {}", DELIMETER
+        );
+        assert!(
+            attributes[..i].iter().all(|other| other.trait_type != attribute.trait_type),
+            "Duplicate trait_type {}", attribute.trait_type
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::*;
+use std::collections::HashSet;
+
+/// bounds the gas of a batch, each token may also move its children
+pub const MAX_BATCH_TRANSFERS: usize = 50;
+
+/// receiver_id, token_id, approval_id, memo
+pub type BatchTransfer = (ValidAccountId, TokenId, Option<u64>, Option<String>);
+
+/// CUSTOM - transfers in one call, one yocto for all of them
+/// one NftTransfer event for the batch and one approval refund per previous owner
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn nft_batch_transfer(&mut self, transfers: Vec<BatchTransfer>) {
+        assert_one_yocto();
+        assert!(!transfers.is_empty(), "No transfers");
+        assert!(transfers.len() <= MAX_BATCH_TRANSFERS, "Cannot transfer more than {} tokens at once", MAX_BATCH_TRANSFERS);
+        let mut token_ids = HashSet::new();
+        for (_, token_id, _, _) in transfers.iter() {
+            assert!(token_ids.insert(token_id), "Duplicate token {}", token_id);
+            assert!(self.tokens_by_id.get(token_id).is_some(), "No token {}", token_id);
+        }
+
+        let sender_id = env::predecessor_account_id();
+        let mut logs = vec![];
+        let mut refunds: HashMap<AccountId, StorageUsage> = HashMap::new();
+        for (receiver_id, token_id, approval_id, memo) in transfers {
+            let previous_token = self.internal_transfer_logged(
+                &sender_id,
+                receiver_id.as_ref(),
+                &token_id,
+                approval_id,
+                memo,
+                None,
+                &mut logs,
+            );
+            let storage_released = previous_token.approved_account_ids.keys().map(bytes_for_approved_account_id).sum::<u64>()
+                + self.internal_release_approval_conditions(&token_id, previous_token.approved_account_ids.keys());
+            if storage_released > 0 {
+                *refunds.entry(previous_token.owner_id).or_insert(0) += storage_released;
+            }
+        }
+
+        EventLog::new(EventLogVariant::NftTransfer(logs)).emit();
+        for (owner_id, storage_released) in refunds {
+            Promise::new(owner_id).transfer(Balance::from(storage_released) * env::storage_byte_cost());
+        }
+    }
+}
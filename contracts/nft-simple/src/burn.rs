@@ -1,14 +1,136 @@
 use crate::*;
 
-/// CUSTOM - owner can burn a locked token for a given user, reducing the enumerable->nft_supply_for_type
+/// CUSTOM - who may burn tokens of a given token_type
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BurnPolicy {
+    /// tokens of this type can't be burned
+    Disabled,
+    /// contract owner can burn a token while its token_type is locked
+    OwnerWhileLocked,
+    /// token holder can burn their own token
+    Holder,
+    /// both of the above
+    OwnerWhileLockedOrHolder,
+}
+
+/// CUSTOM - owner (or an admin) can burn a locked token for a given user, reducing the enumerable->nft_supply_for_type
+/// holders can burn their own tokens, depending on the BurnPolicy of the token_type
+/// burning refunds the holder their approval deposits and the account that paid for the mint its storage
 #[near_bindgen]
 impl Contract {
     #[payable]
     pub fn nft_burn(
         &mut self,
-        token_id: Option<TokenId>,
+        token_id: TokenId,
     ) {
-        assert_eq!(self.is_token_locked(token_id), true, "Token must be locked");
-        //TODO burn token
+        assert_one_yocto();
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        let predecessor_account_id = env::predecessor_account_id();
+        self.assert_not_nested(&token_id);
+        self.assert_not_frozen(&token_id);
+        assert!(self.children_by_token.get(&token_id).is_none(), "Detach children before burning");
+
+        // untyped tokens can always be burned by their holder
+        let burn_policy = token.token_type.as_ref()
+            .map(|token_type| self.internal_token_type(token_type).burn_policy)
+            .unwrap_or(BurnPolicy::Holder);
+        let is_locked = self.internal_is_type_locked(&token.token_type);
+
+        let owner_can_burn = is_locked
+            && self.internal_has_role(&predecessor_account_id, Role::Admin)
+            && matches!(burn_policy, BurnPolicy::OwnerWhileLocked | BurnPolicy::OwnerWhileLockedOrHolder);
+        // soulbound tokens can always be given up by their holder
+        let holder_can_burn = predecessor_account_id == token.owner_id
+            && (matches!(burn_policy, BurnPolicy::Holder | BurnPolicy::OwnerWhileLockedOrHolder)
+                || self.internal_is_non_transferable(&token.token_type));
+        assert!(owner_can_burn || holder_can_burn, "Not allowed to burn token");
+
+        let authorized_id = if predecessor_account_id != token.owner_id {
+            Some(predecessor_account_id)
+        } else {
+            None
+        };
+        self.internal_burn(token_id, token, authorized_id);
+    }
+
+    /// CUSTOM - type manager sets who may burn tokens of a token_type
+    pub fn set_burn_policy(&mut self, token_type: TokenType, burn_policy: BurnPolicy) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        config.burn_policy = burn_policy;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    /// CUSTOM - admin moves a non-transferable token to a new account of the holder, e.g. a lost key
+    #[payable]
+    pub fn nft_recover(&mut self, token_id: TokenId, receiver_id: ValidAccountId, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        assert!(self.internal_is_non_transferable(&token.token_type), "Only non-transferable tokens can be recovered");
+        self.assert_not_nested(&token_id);
+        // frozen tokens are recovered with the delay of nft_recover_frozen
+        self.assert_not_frozen(&token_id);
+
+        self.internal_move_token(&token, receiver_id.as_ref(), &token_id, Some(env::predecessor_account_id()), memo);
+        self.internal_remove_approval_conditions(&token_id, &token.owner_id, token.approved_account_ids.keys());
+        refund_approved_account_ids(token.owner_id, &token.approved_account_ids);
+    }
+
+    pub fn get_burn_policy(&self, token_type: TokenType) -> BurnPolicy {
+        self.internal_token_type(&token_type).burn_policy
+    }
+}
+
+impl Contract {
+    /// removes the token everywhere, callers check who may burn
+    pub(crate) fn internal_burn(&mut self, token_id: TokenId, token: Token, authorized_id: Option<AccountId>) {
+        // marking as expired was paid by whoever called nft_cleanup_expired
+        self.expired_tokens.remove(&token_id);
+        // the renter paid for the user record, clearing it refunds them
+        self.internal_clear_user(&token_id);
+        let initial_storage_usage = env::storage_usage();
+
+        // the holder paid for approvals and their conditions
+        let storage_released = self.internal_release_approval_conditions(&token_id, token.approved_account_ids.keys())
+            + token.approved_account_ids.keys().map(bytes_for_approved_account_id).sum::<u64>();
+
+        self.internal_unindex_attributes(&token_id);
+        self.token_attributes.remove(&token_id);
+        self.internal_index_last_owner_type();
+        self.tokens_by_id.remove(&token_id);
+        self.token_metadata_by_id.remove(&token_id);
+        if let Some(series_id) = self.series_by_token.remove(&token_id) {
+            let mut series = self.series.get(&series_id).expect("No series");
+            series.burned += 1;
+            self.series.insert(&series_id, &series);
+        }
+        self.royalty_history.remove(&token_id);
+        self.pending_royalty_reassignments.remove(&token_id);
+        self.internal_remove_token_from_owner(&token.owner_id, &token_id, token.token_type.as_ref());
+        if let Some(token_type) = token.token_type.as_ref() {
+            self.internal_remove_token_from_type(token_type, &token_id);
+        }
+
+        if storage_released > 0 {
+            Promise::new(token.owner_id.clone()).transfer(Balance::from(storage_released) * env::storage_byte_cost());
+        }
+        // the payer at mint gets the rest of the freed storage, up to what they paid
+        // tokens minted before mint_storage_by_token have no record, their storage stays with the contract
+        if let Some(mint_storage) = self.mint_storage_by_token.remove(&token_id) {
+            let storage_freed = initial_storage_usage.saturating_sub(env::storage_usage()) + self.extra_storage_in_bytes_per_token;
+            let refund = min(mint_storage.storage_used, storage_freed.saturating_sub(storage_released));
+            if refund > 0 {
+                Promise::new(mint_storage.payer_id).transfer(Balance::from(refund) * env::storage_byte_cost());
+            }
+        }
+
+        EventLog::new(EventLogVariant::NftBurn(vec![NftBurnLog {
+            owner_id: token.owner_id,
+            authorized_id,
+            token_ids: vec![token_id],
+            memo: None,
+        }])).emit();
     }
-}
\ No newline at end of file
+}
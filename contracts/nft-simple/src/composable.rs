@@ -0,0 +1,322 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_NFT_TRANSFER: Gas = 15_000_000_000_000;
+const GAS_FOR_RESOLVE_DETACH: Gas = 10_000_000_000_000;
+const NO_DEPOSIT: Balance = 0;
+/// bounds the gas of moving a parent with all of its descendants
+pub const MAX_NESTING_DEPTH: u32 = 4;
+pub const MAX_CHILDREN: usize = 10;
+static DELIMETER: &str = "||";
+
+/// CUSTOM - token owned by another token of this contract
+/// contract_id is this contract for local tokens, or the NFT contract that holds a foreign token
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChildToken {
+    pub contract_id: AccountId,
+    pub token_id: TokenId,
+}
+
+/// msg of nft_transfer_call on a foreign NFT contract to nest a token
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NestArgs {
+    pub parent_id: TokenId,
+}
+
+/// nft-simple requires an approval_id, owners pass 0
+#[ext_contract(ext_foreign_nft)]
+trait ForeignNonFungibleToken {
+    fn nft_transfer(&mut self, receiver_id: AccountId, token_id: TokenId, approval_id: u64, memo: Option<String>);
+}
+
+#[ext_contract(ext_detach_resolver)]
+trait DetachResolver {
+    fn resolve_detach(&mut self, parent_id: TokenId, child: ChildToken);
+}
+
+trait NonFungibleTokenReceiver {
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool>;
+}
+
+/// CUSTOM - nested tokens keep the owner_id of their root token and move with it
+/// only the root owner can detach them, nested tokens can't be transferred, approved or burned directly
+#[near_bindgen]
+impl Contract {
+    /// holder nests their token into parent_id, attach a deposit to cover storage
+    #[payable]
+    pub fn nft_nest(&mut self, token_id: TokenId, parent_id: TokenId) {
+        assert_at_least_one_yocto();
+        self.assert_not_paused(PauseFlag::Transfers);
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        assert_eq!(token.owner_id, predecessor_account_id, "Only the holder can nest a token");
+        self.assert_not_nested(&token_id);
+        self.assert_not_frozen(&token_id);
+        self.assert_token_valid(&token_id, &token);
+        assert!(!self.internal_is_type_locked(&token.token_type), "Token transfers are locked");
+        assert!(!self.internal_is_non_transferable(&token.token_type), "Tokens of this type are non-transferable");
+        assert_eq!(
+            self.internal_root_owner(&parent_id), predecessor_account_id,
+            "Only the root owner of the parent can nest into it"
+        );
+
+        // no cycles and bounded depth
+        let mut depth = self.internal_subtree_depth(&token_id) + 1;
+        let mut ancestor_id = Some(parent_id.clone());
+        while let Some(id) = ancestor_id {
+            assert_ne!(id, token_id, "Cannot nest a token into itself or its children");
+            depth += 1;
+            ancestor_id = self.parent_by_child.get(&child_key(&env::current_account_id(), &id));
+        }
+        assert!(depth <= MAX_NESTING_DEPTH + 1, "Cannot nest more than {} levels", MAX_NESTING_DEPTH);
+
+        // approvals of nested tokens are cleared
+        if !token.approved_account_ids.is_empty() {
+            let mut token = token;
+            refund_approved_account_ids(token.owner_id.clone(), &token.approved_account_ids);
+            self.internal_remove_approval_conditions(&token_id, &token.owner_id, token.approved_account_ids.keys());
+            token.approved_account_ids.clear();
+            self.tokens_by_id.insert(&token_id, &token);
+        }
+
+        self.internal_add_child(&parent_id, ChildToken {
+            contract_id: env::current_account_id(),
+            token_id,
+        });
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_charge_storage(storage_used);
+    }
+
+    /// only root owner - detach a child of parent_id to receiver_id (default the root owner)
+    #[payable]
+    pub fn nft_detach(
+        &mut self,
+        parent_id: TokenId,
+        child: ChildToken,
+        receiver_id: Option<ValidAccountId>,
+    ) -> PromiseOrValue<()> {
+        assert_one_yocto();
+        self.assert_not_paused(PauseFlag::Transfers);
+        let root_owner_id = self.internal_root_owner(&parent_id);
+        assert_eq!(env::predecessor_account_id(), root_owner_id, "Only the root owner can detach");
+        let receiver_id: AccountId = receiver_id.map(|a| a.into()).unwrap_or_else(|| root_owner_id.clone());
+        self.internal_remove_child(&parent_id, &child);
+
+        if child.contract_id == env::current_account_id() {
+            let token = self.tokens_by_id.get(&child.token_id).expect("No token");
+            if token.owner_id != receiver_id {
+                self.assert_not_frozen(&child.token_id);
+                self.assert_children_not_frozen(&child.token_id);
+                self.assert_token_movable(&child.token_id, &token, &root_owner_id);
+                self.assert_children_movable(&child.token_id, &root_owner_id);
+                self.internal_move_token(&token, &receiver_id, &child.token_id, None, None);
+            }
+            return PromiseOrValue::Value(());
+        }
+
+        ext_foreign_nft::nft_transfer(
+            receiver_id,
+            child.token_id.clone(),
+            0,
+            None,
+            &child.contract_id,
+            1,
+            GAS_FOR_NFT_TRANSFER,
+        )
+        .then(ext_detach_resolver::resolve_detach(
+            parent_id,
+            child,
+            &env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_DETACH,
+        )).into()
+    }
+
+    /// self callback, the child stays nested if the foreign transfer failed
+    #[private]
+    pub fn resolve_detach(&mut self, parent_id: TokenId, child: ChildToken) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+        if self.tokens_by_id.get(&parent_id).is_some() {
+            self.internal_add_child(&parent_id, child);
+        }
+    }
+
+    /// only admin - NFT contracts whose tokens can be nested with nft_transfer_call
+    /// nft_on_transfer trusts their sender_id and previous_owner_id
+
+    pub fn add_nest_contract_ids(&mut self, contract_ids: Vec<ValidAccountId>) -> Vec<bool> {
+        self.assert_role(Role::Admin);
+        contract_ids.iter().map(|contract_id| self.nest_contract_ids.insert(contract_id.as_ref())).collect()
+    }
+
+    /// nested tokens of a removed contract stay nested until detached
+    pub fn remove_nest_contract_ids(&mut self, contract_ids: Vec<ValidAccountId>) -> Vec<bool> {
+        self.assert_role(Role::Admin);
+        contract_ids.iter().map(|contract_id| self.nest_contract_ids.remove(contract_id.as_ref())).collect()
+    }
+
+    /// views
+
+    pub fn get_nest_contract_ids(&self) -> Vec<AccountId> {
+        self.nest_contract_ids.to_vec()
+    }
+
+    pub fn nft_children(&self, token_id: TokenId) -> Vec<ChildToken> {
+        self.children_by_token.get(&token_id).unwrap_or_default()
+    }
+
+    /// contract_id defaults to this contract
+    pub fn nft_parent(&self, token_id: TokenId, contract_id: Option<AccountId>) -> Option<TokenId> {
+        let contract_id = contract_id.unwrap_or_else(env::current_account_id);
+        self.parent_by_child.get(&child_key(&contract_id, &token_id))
+    }
+
+    /// owner of the top level token, contract_id defaults to this contract
+    pub fn nft_root_owner(&self, token_id: TokenId, contract_id: Option<AccountId>) -> Option<AccountId> {
+        let contract_id = contract_id.unwrap_or_else(env::current_account_id);
+        if contract_id == env::current_account_id() {
+            self.tokens_by_id.get(&token_id).map(|token| token.owner_id)
+        } else {
+            self.parent_by_child.get(&child_key(&contract_id, &token_id))
+                .map(|parent_id| self.internal_root_owner(&parent_id))
+        }
+    }
+}
+
+/// foreign NFT contracts nest a token with nft_transfer_call and msg { "parent_id": "..." }
+/// only contracts of nest_contract_ids, any account can call this claiming to be an NFT contract
+/// the token is returned unless its holder sent it and is the root owner of the parent
+/// the holder pays the storage from their storage_deposit balance, the token is returned if it's too low
+#[near_bindgen]
+impl NonFungibleTokenReceiver for Contract {
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let contract_id = env::predecessor_account_id();
+        // tokens of this contract are nested with nft_nest
+        if contract_id == env::current_account_id() || !self.nest_contract_ids.contains(&contract_id) {
+            return PromiseOrValue::Value(true);
+        }
+        let parent_id = if let Ok(NestArgs { parent_id }) = near_sdk::serde_json::from_str(&msg) {
+            parent_id
+        } else {
+            return PromiseOrValue::Value(true);
+        };
+        // only the holder nests, not an approved account
+        let is_root_owner = sender_id == previous_owner_id && self.tokens_by_id.get(&parent_id)
+            .map(|parent| parent.owner_id == previous_owner_id)
+            .unwrap_or(false);
+        let children = self.children_by_token.get(&parent_id).unwrap_or_default();
+        if !is_root_owner || children.len() >= MAX_CHILDREN {
+            return PromiseOrValue::Value(true);
+        }
+
+        let initial_storage_usage = env::storage_usage();
+        let child = ChildToken { contract_id, token_id };
+        self.internal_add_child(&parent_id, child.clone());
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        if !self.internal_try_charge_storage_balance(&previous_owner_id, storage_used) {
+            self.internal_remove_child(&parent_id, &child);
+            return PromiseOrValue::Value(true);
+        }
+        PromiseOrValue::Value(false)
+    }
+}
+
+impl Contract {
+    /// owner_id of local tokens is always their root owner
+    pub(crate) fn internal_root_owner(&self, token_id: &TokenId) -> AccountId {
+        self.tokens_by_id.get(token_id).expect("No token").owner_id
+    }
+
+    pub(crate) fn assert_not_nested(&self, token_id: &TokenId) {
+        assert!(
+            self.parent_by_child.get(&child_key(&env::current_account_id(), token_id)).is_none(),
+            "Token is nested, detach it first"
+        );
+    }
+
+    /// moves local children, and their children, along with their parent, pushing their NftTransfer logs
+    /// transfers check them with assert_children_movable, admin recoveries and reverted transfers move them as is
+    pub(crate) fn internal_move_children(
+        &mut self,
+        token_id: &TokenId,
+        receiver_id: &AccountId,
+        authorized_id: Option<AccountId>,
+        logs: &mut Vec<NftTransferLog>,
+    ) {
+        let current_account_id = env::current_account_id();
+        for child in self.children_by_token.get(token_id).unwrap_or_default() {
+            if child.contract_id != current_account_id {
+                continue;
+            }
+            let token = self.tokens_by_id.get(&child.token_id).expect("No token");
+            if &token.owner_id != receiver_id {
+                self.internal_move_token_logged(&token, receiver_id, &child.token_id, authorized_id.clone(), None, logs);
+            }
+        }
+    }
+
+    /// local children move with their parent, so each one must be movable by sender_id
+    pub(crate) fn assert_children_movable(&self, token_id: &TokenId, sender_id: &AccountId) {
+        let current_account_id = env::current_account_id();
+        for child in self.children_by_token.get(token_id).unwrap_or_default() {
+            if child.contract_id != current_account_id {
+                continue;
+            }
+            let token = self.tokens_by_id.get(&child.token_id).expect("No token");
+            self.assert_token_movable(&child.token_id, &token, sender_id);
+            self.assert_children_movable(&child.token_id, sender_id);
+        }
+    }
+
+    fn internal_subtree_depth(&self, token_id: &TokenId) -> u32 {
+        let current_account_id = env::current_account_id();
+        self.children_by_token.get(token_id).unwrap_or_default().iter()
+            .filter(|child| child.contract_id == current_account_id)
+            .map(|child| self.internal_subtree_depth(&child.token_id) + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn internal_add_child(&mut self, parent_id: &TokenId, child: ChildToken) {
+        let mut children = self.children_by_token.get(parent_id).unwrap_or_default();
+        assert!(children.len() < MAX_CHILDREN, "Cannot have more than {} children", MAX_CHILDREN);
+        self.parent_by_child.insert(&child_key(&child.contract_id, &child.token_id), parent_id);
+        children.push(child);
+        self.children_by_token.insert(parent_id, &children);
+    }
+
+    fn internal_remove_child(&mut self, parent_id: &TokenId, child: &ChildToken) {
+        let mut children = self.children_by_token.get(parent_id).unwrap_or_default();
+        let len = children.len();
+        children.retain(|c| c != child);
+        assert!(children.len() < len, "Not a child of the parent");
+        if children.is_empty() {
+            self.children_by_token.remove(parent_id);
+        } else {
+            self.children_by_token.insert(parent_id, &children);
+        }
+        self.parent_by_child.remove(&child_key(&child.contract_id, &child.token_id));
+    }
+}
+
+fn child_key(contract_id: &AccountId, token_id: &TokenId) -> String {
+    format!("{}{}{}", contract_id, DELIMETER, token_id)
+}
@@ -1,4 +1,23 @@
 use crate::*;
+use near_sdk::collections::Vector;
+use std::convert::TryInto;
+
+/// page size when no limit is given
+pub const DEFAULT_PAGE_LIMIT: u64 = 50;
+/// larger limits are capped to keep views within gas
+pub const MAX_PAGE_LIMIT: u64 = 100;
+static DELIMETER: &str = "||";
+
+/// CUSTOM - a page of tokens, pass next_cursor to get the next page, None is the last page
+/// cursors are opaque to clients
+/// a cursor is a position in the collection, removing a token swaps the last token into its slot,
+/// so a transfer or burn between pages can skip a token or return one twice
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenPage {
+    pub tokens: Vec<JsonToken>,
+    pub next_cursor: Option<Base64VecU8>,
+}
 
 #[near_bindgen]
 impl Contract {
@@ -8,41 +27,33 @@ impl Contract {
         let start = u128::from(from_index.unwrap_or(U128(0)));
         keys.iter()
             .skip(start as usize)
-            .take(limit.unwrap_or(0) as usize)
-            .map(|token_id| self.nft_token(token_id.clone()).unwrap())
+            .take(page_limit(limit) as usize)
+            .map(|token_id| self.nft_token(token_id).unwrap())
             .collect()
     }
 
+    /// missing tokens are null
     pub fn nft_tokens_batch(
         &self,
         token_ids: Vec<String>,
-    ) -> Vec<JsonToken> {
-        let mut tmp = vec![];
-        for i in 0..token_ids.len() {
-            tmp.push(self.nft_token(token_ids[i].clone()).unwrap());
-        }
-        tmp
+    ) -> Vec<Option<JsonToken>> {
+        assert!(token_ids.len() as u64 <= MAX_PAGE_LIMIT, "Cannot get more than {} tokens", MAX_PAGE_LIMIT);
+        token_ids.into_iter().map(|token_id| self.nft_token(token_id)).collect()
     }
-    
+
     pub fn nft_supply_for_type(
         &self,
-        token_type: &String,
-    ) -> U64 {
-        let tokens_per_type = self.tokens_per_type.get(&token_type);
-        if let Some(tokens_per_type) = tokens_per_type {
-            U64(tokens_per_type.len())
-        } else {
-            U64(0)
-        }
+        token_type: String,
+    ) -> U128 {
+        U128(self.internal_supply_for_type(&token_type) as u128)
     }
 
     pub fn nft_tokens_for_type(
         &self,
         token_type: String,
-        from_index: U64,
-        limit: u64,
+        from_index: Option<U128>,
+        limit: Option<u64>,
     ) -> Vec<JsonToken> {
-        let mut tmp = vec![];
         let tokens_per_type = self.tokens_per_type.get(&token_type);
         let tokens = if let Some(tokens_per_type) = tokens_per_type {
             tokens_per_type
@@ -50,14 +61,14 @@ impl Contract {
             return vec![];
         };
         let keys = tokens.as_vector();
-        let start = u64::from(from_index);
-        let end = min(start + limit, keys.len());
-        for i in start..end {
-            tmp.push(self.nft_token(keys.get(i).unwrap()).unwrap());
-        }
-        tmp
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        keys.iter()
+            .skip(start as usize)
+            .take(page_limit(limit) as usize)
+            .map(|token_id| self.nft_token(token_id).unwrap())
+            .collect()
     }
-    
+
     pub fn nft_supply_for_owner(
         &self,
         account_id: AccountId,
@@ -86,8 +97,167 @@ impl Contract {
         let start = u128::from(from_index.unwrap_or(U128(0)));
         keys.iter()
             .skip(start as usize)
-            .take(limit.unwrap_or(0) as usize)
-            .map(|token_id| self.nft_token(token_id.clone()).unwrap())
+            .take(page_limit(limit) as usize)
+            .map(|token_id| self.nft_token(token_id).unwrap())
             .collect()
     }
+
+    /// CUSTOM - owner + type
+
+    pub fn nft_supply_for_owner_by_type(&self, account_id: AccountId, token_type: String) -> U128 {
+        self.assert_owner_types_indexed();
+        U128(self.tokens_per_owner_type.get(&owner_type_key(&account_id, &token_type))
+            .map(|tokens_set| tokens_set.len())
+            .unwrap_or(0) as u128)
+    }
+
+    pub fn nft_tokens_for_owner_by_type(
+        &self,
+        account_id: AccountId,
+        token_type: String,
+        cursor: Option<Base64VecU8>,
+        limit: Option<u64>,
+    ) -> TokenPage {
+        self.assert_owner_types_indexed();
+        match self.tokens_per_owner_type.get(&owner_type_key(&account_id, &token_type)) {
+            Some(tokens_set) => self.internal_token_page(tokens_set.as_vector(), cursor, limit, |_| true),
+            None => TokenPage { tokens: vec![], next_cursor: None },
+        }
+    }
+
+    /// only admin - adds up to limit tokens minted before the upgrade to tokens_per_owner_type
+    /// call until it returns None, the owner + type views are unavailable until then
+    pub fn nft_index_owner_types(&mut self, limit: Option<u64>) -> Option<U64> {
+        self.assert_role(Role::Admin);
+        let mut index = self.owner_type_index_cursor.expect("Every token is indexed");
+        let len = self.token_metadata_by_id.len();
+        let end = min(index + page_limit(limit), len);
+        while index < end {
+            let token_id = self.token_metadata_by_id.keys_as_vector().get(index).unwrap();
+            self.internal_index_owner_type(&token_id);
+            index += 1;
+        }
+        self.owner_type_index_cursor = if index < len { Some(index) } else { None };
+        self.owner_type_index_cursor.map(U64)
+    }
+
+    /// CUSTOM - cursor pagination
+
+    pub fn nft_tokens_page(&self, cursor: Option<Base64VecU8>, limit: Option<u64>) -> TokenPage {
+        self.internal_token_page(self.token_metadata_by_id.keys_as_vector(), cursor, limit, |_| true)
+    }
+
+    pub fn nft_tokens_for_owner_page(
+        &self,
+        account_id: AccountId,
+        cursor: Option<Base64VecU8>,
+        limit: Option<u64>,
+    ) -> TokenPage {
+        match self.tokens_per_owner.get(&account_id) {
+            Some(tokens_owner) => self.internal_token_page(tokens_owner.as_vector(), cursor, limit, |_| true),
+            None => TokenPage { tokens: vec![], next_cursor: None },
+        }
+    }
+
+    pub fn nft_tokens_for_type_page(
+        &self,
+        token_type: String,
+        cursor: Option<Base64VecU8>,
+        limit: Option<u64>,
+    ) -> TokenPage {
+        match self.tokens_per_type.get(&token_type) {
+            Some(tokens_per_type) => self.internal_token_page(tokens_per_type.as_vector(), cursor, limit, |_| true),
+            None => TokenPage { tokens: vec![], next_cursor: None },
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_supply_for_type(&self, token_type: &TokenType) -> u64 {
+        self.tokens_per_type.get(token_type).map(|tokens_per_type| tokens_per_type.len()).unwrap_or(0)
+    }
+
+    pub(crate) fn internal_add_token_to_owner_type(&mut self, account_id: &AccountId, token_id: &TokenId, token_type: &TokenType) {
+        let key = owner_type_key(account_id, token_type);
+        let mut tokens_set = self.tokens_per_owner_type.get(&key).unwrap_or_else(|| {
+            UnorderedSet::new(
+                StorageKey::TokensPerOwnerTypeInner {
+                    owner_type_hash: hash_account_id(&key),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+        });
+        tokens_set.insert(token_id);
+        self.tokens_per_owner_type.insert(&key, &tokens_set);
+    }
+
+    /// adds a token to the set of its current owner and type, adding it twice is a no-op
+    fn internal_index_owner_type(&mut self, token_id: &TokenId) {
+        let token = self.tokens_by_id.get(token_id).expect("No token");
+        if let Some(token_type) = token.token_type.as_ref() {
+            self.internal_add_token_to_owner_type(&token.owner_id, token_id, token_type);
+        }
+    }
+
+    /// removing a token swaps the last key of token_metadata_by_id into its slot,
+    /// index that key first if the backfill has not reached it or it would be skipped
+    pub(crate) fn internal_index_last_owner_type(&mut self) {
+        let index = if let Some(index) = self.owner_type_index_cursor {
+            index
+        } else {
+            return;
+        };
+        let len = self.token_metadata_by_id.len();
+        if len > index {
+            let token_id = self.token_metadata_by_id.keys_as_vector().get(len - 1).unwrap();
+            self.internal_index_owner_type(&token_id);
+        }
+    }
+
+    fn assert_owner_types_indexed(&self) {
+        assert!(self.owner_type_index_cursor.is_none(), "Tokens are being indexed by owner and type, see nft_index_owner_types");
+    }
+
+    /// tokens of keys from the cursor that match filter, the cursor is the next index of keys to read
+    fn internal_token_page<F: Fn(&TokenId) -> bool>(
+        &self,
+        keys: &Vector<TokenId>,
+        cursor: Option<Base64VecU8>,
+        limit: Option<u64>,
+        filter: F,
+    ) -> TokenPage {
+        let limit = page_limit(limit) as usize;
+        let mut index = cursor.map(cursor_to_index).unwrap_or(0);
+        let mut tokens = vec![];
+        while index < keys.len() && tokens.len() < limit {
+            let token_id = keys.get(index).unwrap();
+            index += 1;
+            if filter(&token_id) {
+                tokens.push(self.nft_token(token_id).unwrap());
+            }
+        }
+        TokenPage {
+            tokens,
+            next_cursor: if index < keys.len() { Some(index_to_cursor(index)) } else { None },
+        }
+    }
+}
+
+/// key of tokens_per_owner_type
+pub(crate) fn owner_type_key(account_id: &AccountId, token_type: &str) -> String {
+    format!("{}{}{}", account_id, DELIMETER, token_type)
+}
+
+pub(crate) fn page_limit(limit: Option<u64>) -> u64 {
+    min(limit.unwrap_or(DEFAULT_PAGE_LIMIT), MAX_PAGE_LIMIT)
+}
+
+fn index_to_cursor(index: u64) -> Base64VecU8 {
+    Base64VecU8(index.to_le_bytes().to_vec())
+}
+
+fn cursor_to_index(cursor: Base64VecU8) -> u64 {
+    let bytes: [u8; 8] = cursor.0.as_slice().try_into().expect("Invalid cursor");
+    u64::from_le_bytes(bytes)
 }
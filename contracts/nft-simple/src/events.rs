@@ -0,0 +1,132 @@
+use crate::*;
+use nep297::EventStandard;
+
+/// NEP-297 event standard and version for this contract
+pub const NFT_STANDARD_NAME: &str = "nep171";
+pub const NFT_METADATA_SPEC: &str = "1.0.0";
+
+/// Enum that represents the data type of the EventLog.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+#[serde(crate = "near_sdk::serde")]
+#[non_exhaustive]
+pub enum EventLogVariant {
+    NftMint(Vec<NftMintLog>),
+    NftTransfer(Vec<NftTransferLog>),
+    NftBurn(Vec<NftBurnLog>),
+    /// CUSTOM - not part of NEP-171, rental user set or cleared
+    NftUpdateUser(Vec<NftUpdateUserLog>),
+    /// CUSTOM - not part of NEP-171, token frozen or unfrozen by an admin
+    NftFreeze(Vec<NftFreezeLog>),
+    NftUnfreeze(Vec<NftFreezeLog>),
+}
+
+/// NEP-297 event log of this contract, see the nep297 crate
+pub type EventLog = nep297::EventLog<EventLogVariant>;
+
+impl EventStandard for EventLogVariant {
+    const STANDARD: &'static str = NFT_STANDARD_NAME;
+    const VERSION: &'static str = NFT_METADATA_SPEC;
+
+    fn log(message: &str) {
+        env::log(message.as_bytes());
+    }
+}
+
+/// An event log to capture token minting
+///
+/// Arguments
+/// * `owner_id`: "account.near"
+/// * `token_ids`: ["1", "abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftMintLog {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture token transfer
+///
+/// Arguments
+/// * `authorized_id`: approved account to transfer
+/// * `old_owner_id`: "owner.near"
+/// * `new_owner_id`: "receiver.near"
+/// * `token_ids`: ["1", "12345abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftTransferLog {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture token burning
+///
+/// Arguments
+/// * `owner_id`: owner of the burned tokens
+/// * `authorized_id`: account that burned on behalf of the owner
+/// * `token_ids`: ["1", "abc"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftBurnLog {
+    pub owner_id: AccountId,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+
+    pub token_ids: Vec<TokenId>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture a change of the rental user of a token
+///
+/// Arguments
+/// * `authorized_id`: approved account that set the user
+/// * `owner_id`: owner of the token
+/// * `user_id`: new user, none when cleared
+/// * `expires`: ms timestamp until the user can use the token
+/// * `token_id`: "1"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftUpdateUserLog {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<AccountId>,
+
+    pub owner_id: AccountId,
+    pub user_id: Option<AccountId>,
+    pub expires: U64,
+    pub token_id: TokenId,
+}
+
+/// An event log to capture a token being frozen or unfrozen
+///
+/// Arguments
+/// * `authorized_id`: admin that froze or unfroze the token
+/// * `owner_id`: owner of the token, the receiver when unfrozen by a recovery
+/// * `token_id`: "1"
+/// * `reason`: why the token was frozen, none when unfrozen
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftFreezeLog {
+    pub authorized_id: AccountId,
+    pub owner_id: AccountId,
+    pub token_id: TokenId,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
@@ -0,0 +1,126 @@
+use crate::*;
+
+/// ms between scheduling the recovery of a frozen token and executing it, the holder can dispute it meanwhile
+pub const FROZEN_RECOVERY_DELAY: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// CUSTOM - a single token frozen by an admin, e.g. reported stolen or under dispute
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FrozenToken {
+    pub reason: String,
+    pub frozen_at: U64,
+    /// rightful owner the token is recovered to, see nft_schedule_recovery
+    pub recovery_receiver_id: Option<AccountId>,
+    /// ms timestamp from which nft_recover_frozen can move the token
+    pub recovery_available_at: Option<U64>,
+}
+
+/// CUSTOM - frozen tokens can't be transferred, approved, rented or burned, type wide locks are token_types_locked
+/// an admin returns a frozen token to its rightful owner after FROZEN_RECOVERY_DELAY
+#[near_bindgen]
+impl Contract {
+    /// only admin
+
+    pub fn nft_freeze(&mut self, token_id: TokenId, reason: String) {
+        self.assert_role(Role::Admin);
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        assert!(self.frozen_tokens.get(&token_id).is_none(), "Token is already frozen");
+        self.frozen_tokens.insert(&token_id, &FrozenToken {
+            reason: reason.clone(),
+            frozen_at: U64(env::block_timestamp() / 1_000_000),
+            recovery_receiver_id: None,
+            recovery_available_at: None,
+        });
+        EventLog::new(EventLogVariant::NftFreeze(vec![NftFreezeLog {
+            authorized_id: env::predecessor_account_id(),
+            owner_id: token.owner_id,
+            token_id,
+            reason: Some(reason),
+        }])).emit();
+    }
+
+    /// also cancels a scheduled recovery
+    pub fn nft_unfreeze(&mut self, token_id: TokenId) {
+        self.assert_role(Role::Admin);
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        self.frozen_tokens.remove(&token_id).expect("Token is not frozen");
+        EventLog::new(EventLogVariant::NftUnfreeze(vec![NftFreezeLog {
+            authorized_id: env::predecessor_account_id(),
+            owner_id: token.owner_id,
+            token_id,
+            reason: None,
+        }])).emit();
+    }
+
+    /// scheduling again replaces receiver_id and restarts the delay
+    pub fn nft_schedule_recovery(&mut self, token_id: TokenId, receiver_id: ValidAccountId) -> U64 {
+        self.assert_role(Role::Admin);
+        let mut frozen = self.frozen_tokens.get(&token_id).expect("Token is not frozen");
+        let available_at = U64(env::block_timestamp() / 1_000_000 + FROZEN_RECOVERY_DELAY);
+        frozen.recovery_receiver_id = Some(receiver_id.into());
+        frozen.recovery_available_at = Some(available_at);
+        self.frozen_tokens.insert(&token_id, &frozen);
+        available_at
+    }
+
+    /// moves the frozen token to the scheduled receiver and unfreezes it
+    #[payable]
+    pub fn nft_recover_frozen(&mut self, token_id: TokenId, memo: Option<String>) {
+        assert_one_yocto();
+        self.assert_role(Role::Admin);
+        let frozen = self.frozen_tokens.get(&token_id).expect("Token is not frozen");
+        let receiver_id = frozen.recovery_receiver_id.expect("No recovery scheduled");
+        let available_at = frozen.recovery_available_at.expect("No recovery scheduled");
+        assert!(
+            env::block_timestamp() / 1_000_000 >= available_at.0,
+            "Recovery is available at {}",
+            available_at.0
+        );
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        self.assert_not_nested(&token_id);
+        self.frozen_tokens.remove(&token_id);
+
+        let authorized_id = env::predecessor_account_id();
+        if token.owner_id != receiver_id {
+            self.internal_move_token(&token, &receiver_id, &token_id, Some(authorized_id.clone()), memo);
+            self.internal_remove_approval_conditions(&token_id, &token.owner_id, token.approved_account_ids.keys());
+            refund_approved_account_ids(token.owner_id, &token.approved_account_ids);
+        }
+        EventLog::new(EventLogVariant::NftUnfreeze(vec![NftFreezeLog {
+            authorized_id,
+            owner_id: receiver_id,
+            token_id,
+            reason: None,
+        }])).emit();
+    }
+
+    /// views
+
+    pub fn is_token_frozen(&self, token_id: TokenId) -> bool {
+        self.frozen_tokens.get(&token_id).is_some()
+    }
+
+    pub fn get_token_freeze(&self, token_id: TokenId) -> Option<FrozenToken> {
+        self.frozen_tokens.get(&token_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_not_frozen(&self, token_id: &TokenId) {
+        if let Some(frozen) = self.frozen_tokens.get(token_id) {
+            env::panic(format!("Token is frozen: {}", frozen.reason).as_bytes());
+        }
+    }
+
+    /// local children move with their parent, so a frozen child also stops its parent
+    pub(crate) fn assert_children_not_frozen(&self, token_id: &TokenId) {
+        let current_account_id = env::current_account_id();
+        for child in self.children_by_token.get(token_id).unwrap_or_default() {
+            if child.contract_id != current_account_id {
+                continue;
+            }
+            assert!(self.frozen_tokens.get(&child.token_id).is_none(), "Nested token {} is frozen", child.token_id);
+            self.assert_children_not_frozen(&child.token_id);
+        }
+    }
+}
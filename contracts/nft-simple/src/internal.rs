@@ -1,5 +1,5 @@
 use crate::*;
-use near_sdk::{log, CryptoHash};
+use near_sdk::CryptoHash;
 use std::mem::size_of;
 
 pub(crate) fn royalty_to_payout(a: u32, b: Balance) -> U128 {
@@ -27,23 +27,6 @@ pub(crate) fn assert_at_least_one_yocto() {
     )
 }
 
-pub(crate) fn refund_deposit(storage_used: u64) {
-    let required_cost = env::storage_byte_cost() * Balance::from(storage_used);
-    let attached_deposit = env::attached_deposit();
-
-    assert!(
-        required_cost <= attached_deposit,
-        "Must attach {} yoctoNEAR to cover storage",
-        required_cost,
-    );
-
-    let refund = attached_deposit - required_cost;
-    if refund > 1 {
-        Promise::new(env::predecessor_account_id()).transfer(refund);
-    }
-}
-
-// TODO: need a way for end users to determine how much an approval will cost.
 pub(crate) fn bytes_for_approved_account_id(account_id: &AccountId) -> u64 {
     // The extra 4 bytes are coming from Borsh serialization to store the length of the string.
     account_id.len() as u64 + 4 + size_of::<u64>() as u64
@@ -70,6 +53,7 @@ pub(crate) fn refund_approved_account_ids(
 }
 
 impl Contract {
+    /// the contract owner only, roles don't grant this
     pub(crate) fn assert_owner(&self) {
         assert_eq!(
             &env::predecessor_account_id(),
@@ -78,15 +62,101 @@ impl Contract {
         );
     }
 
+    pub(crate) fn internal_charge_storage(&mut self, storage_used: u64) {
+        self.internal_charge_storage_with_cost(storage_used, 0)
+    }
+
+    /// CUSTOM - the attached deposit pays a price in NEAR and storage, refunding what's left
+    /// storage not covered by the attached deposit is taken from the storage_deposit balance of the predecessor
+    pub(crate) fn internal_charge_storage_with_cost(&mut self, storage_used: u64, cost: Balance) {
+        self.internal_charge_storage_refund_to(storage_used, cost, env::predecessor_account_id())
+    }
+
+    /// CUSTOM - internal_charge_storage_with_cost that refunds what's left of the deposit to refund_id
+    pub(crate) fn internal_charge_storage_refund_to(&mut self, storage_used: u64, cost: Balance, refund_id: AccountId) {
+        let required_cost = env::storage_byte_cost() * Balance::from(storage_used) + cost;
+        let attached_deposit = env::attached_deposit();
+        let account_id = env::predecessor_account_id();
+
+        if required_cost <= attached_deposit {
+            let refund = attached_deposit - required_cost;
+            if refund > 1 {
+                Promise::new(refund_id).transfer(refund);
+            }
+            return;
+        }
+
+        assert!(cost <= attached_deposit, "Must attach {} yoctoNEAR to cover price", cost);
+        let shortfall = required_cost - attached_deposit;
+        let available = self.internal_storage_available(&account_id);
+        assert!(
+            shortfall <= available,
+            "Must attach {} yoctoNEAR or deposit it with storage_deposit to cover storage and price",
+            shortfall - available,
+        );
+        let balance = self.storage_deposits.get(&account_id).unwrap_or(0);
+        self.storage_deposits.insert(&account_id, &(balance - shortfall));
+    }
+
+    /// CUSTOM - storage of calls without a NEAR deposit, like FT and NFT transfer callbacks
+    /// takes it from the storage_deposit balance of account_id, returns false without charging if that's too low
+    pub(crate) fn internal_try_charge_storage_balance(&mut self, account_id: &AccountId, storage_used: u64) -> bool {
+        let cost = env::storage_byte_cost() * Balance::from(storage_used);
+        if cost > self.internal_storage_available(account_id) {
+            return false;
+        }
+        let balance = self.storage_deposits.get(account_id).unwrap_or(0);
+        self.storage_deposits.insert(account_id, &(balance - cost));
+        true
+    }
+
+    /// CUSTOM - payout engine of nft_payout and nft_transfer_payout
+    /// royalties are rounded down and the token owner receives the remainder, so the payout sums to balance
+    /// if there are more receivers than max_len_payout, the smallest royalties are dropped and go to the owner
+    pub(crate) fn internal_payout(&self, token: &Token, balance: Balance, max_len_payout: u32) -> Payout {
+        assert!(max_len_payout > 0, "Market cannot payout to that many receivers");
+        let owner_id = &token.owner_id;
+
+        let mut royalty: HashMap<AccountId, u32> = token.royalty.iter()
+            .filter(|(account_id, _)| *account_id != owner_id)
+            .map(|(account_id, amount)| (account_id.clone(), *amount))
+            .collect();
+        // payout to contract owner - may be previous token owner, they get remainder of balance
+        if self.contract_royalty > 0 && &self.owner_id != owner_id {
+            *royalty.entry(self.owner_id.clone()).or_insert(0) += self.contract_royalty;
+        }
+        assert!(
+            royalty.values().sum::<u32>() <= MINTER_ROYALTY_CAP + CONTRACT_ROYALTY_CAP,
+            "Royalties should not be more than caps"
+        );
+
+        // largest royalties first, ties by account so the result doesn't depend on map order
+        let mut royalty: Vec<(AccountId, u32)> = royalty.into_iter().collect();
+        royalty.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then_with(|| a_id.cmp(b_id)));
+        royalty.truncate(max_len_payout as usize - 1);
+
+        let mut payout = HashMap::new();
+        let mut remainder = balance;
+        for (account_id, amount) in royalty {
+            let amount = royalty_to_payout(amount, balance);
+            remainder -= amount.0;
+            payout.insert(account_id, amount);
+        }
+        payout.insert(owner_id.clone(), U128(remainder));
+
+        Payout { payout }
+    }
+
     pub(crate) fn internal_add_token_to_owner(
         &mut self,
         account_id: &AccountId,
         token_id: &TokenId,
+        token_type: Option<&TokenType>,
     ) {
         let mut tokens_set = self.tokens_per_owner.get(account_id).unwrap_or_else(|| {
             UnorderedSet::new(
                 StorageKey::TokenPerOwnerInner {
-                    account_id_hash: hash_account_id(&account_id),
+                    account_id_hash: hash_account_id(account_id),
                 }
                 .try_to_vec()
                 .unwrap(),
@@ -94,12 +164,16 @@ impl Contract {
         });
         tokens_set.insert(token_id);
         self.tokens_per_owner.insert(account_id, &tokens_set);
+        if let Some(token_type) = token_type {
+            self.internal_add_token_to_owner_type(account_id, token_id, token_type);
+        }
     }
 
     pub(crate) fn internal_remove_token_from_owner(
         &mut self,
         account_id: &AccountId,
         token_id: &TokenId,
+        token_type: Option<&TokenType>,
     ) {
         let mut tokens_set = self
             .tokens_per_owner
@@ -111,6 +185,35 @@ impl Contract {
         } else {
             self.tokens_per_owner.insert(account_id, &tokens_set);
         }
+        if let Some(token_type) = token_type {
+            let key = owner_type_key(account_id, token_type);
+            // tokens not yet backfilled by nft_index_owner_types are not in the set
+            if let Some(mut tokens_set) = self.tokens_per_owner_type.get(&key) {
+                tokens_set.remove(token_id);
+                if tokens_set.is_empty() {
+                    self.tokens_per_owner_type.remove(&key);
+                } else {
+                    self.tokens_per_owner_type.insert(&key, &tokens_set);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn internal_remove_token_from_type(
+        &mut self,
+        token_type: &TokenType,
+        token_id: &TokenId,
+    ) {
+        let mut tokens_set = self
+            .tokens_per_type
+            .get(token_type)
+            .expect("Token should have a type");
+        tokens_set.remove(token_id);
+        if tokens_set.is_empty() {
+            self.tokens_per_type.remove(token_type);
+        } else {
+            self.tokens_per_type.insert(token_type, &tokens_set);
+        }
     }
 
     pub(crate) fn internal_transfer(
@@ -120,17 +223,46 @@ impl Contract {
         token_id: &TokenId,
         approval_id: Option<u64>,
         memo: Option<String>,
+        balance: Option<Balance>,
     ) -> Token {
+        let mut logs = vec![];
+        let token = self.internal_transfer_logged(sender_id, receiver_id, token_id, approval_id, memo, balance, &mut logs);
+        EventLog::new(EventLogVariant::NftTransfer(logs)).emit();
+        token
+    }
+
+    /// CUSTOM - internal_transfer that pushes its NftTransfer logs to logs instead of emitting them
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn internal_transfer_logged(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: Option<Balance>,
+        logs: &mut Vec<NftTransferLog>,
+    ) -> Token {
+        self.assert_not_paused(PauseFlag::Transfers);
         let token = self.tokens_by_id.get(token_id).expect("No token");
 
-        // CUSTOM - token_type can be locked until unlocked by owner
-        if token.token_type.is_some() {
-            assert_eq!(self.token_types_locked.contains(&token.token_type.clone().unwrap()), false, "Token transfers are locked");
-        }
+        // CUSTOM - nested tokens move with their parent
+        self.assert_not_nested(token_id);
+
+        // CUSTOM - frozen tokens stay put until unfrozen or recovered
+        self.assert_not_frozen(token_id);
+        self.assert_children_not_frozen(token_id);
+
+        self.assert_token_movable(token_id, &token, sender_id);
+        self.assert_children_movable(token_id, sender_id);
 
         
 
-		if sender_id != &token.owner_id {
+		// CUSTOM - operators of the owner don't need an approval of the token
+		let is_operator = !token.approved_account_ids.contains_key(sender_id)
+			&& self.internal_is_operator(&token.owner_id, sender_id, &token.token_type);
+
+		if sender_id != &token.owner_id && !is_operator {
 			if !token.approved_account_ids.contains_key(sender_id) {
 				env::panic(b"Unauthorized");
 			}
@@ -146,23 +278,68 @@ impl Contract {
 					actual_approval_id, enforced_approval_id,
 				);
 			}
+			// CUSTOM - approvals can expire and require a minimum sale price
+			self.assert_approval_conditions(token_id, &token, sender_id, balance);
 		}
         
 
+        let authorized_id = if sender_id != &token.owner_id {
+            Some(sender_id.clone())
+        } else {
+            None
+        };
+        self.internal_move_token_logged(&token, receiver_id, token_id, authorized_id, memo, logs);
+
+        token
+    }
+
+    /// CUSTOM - validity window and token_type transfer policy of a token that sender_id moves
+    pub(crate) fn assert_token_movable(&self, token_id: &TokenId, token: &Token, sender_id: &AccountId) {
+        // tokens can be restricted to their starts_at / expires_at window
+        self.assert_token_valid(token_id, token);
+
+        // token_type can be locked until unlocked by owner
+        if let Some(token_type) = token.token_type.as_ref() {
+            let config = self.internal_token_type(token_type);
+            assert!(config.transfer_policy != TransferPolicy::NonTransferable, "Tokens of this type are non-transferable");
+            assert!(!config.is_locked(), "Token transfers are locked");
+            if config.transfer_policy == TransferPolicy::HolderOnly {
+                assert_eq!(sender_id, &token.owner_id, "Only the holder can transfer tokens of this type");
+            }
+        }
+    }
+
+    /// moves the token to receiver_id and clears approvals, callers check who may move it
+    pub(crate) fn internal_move_token(
+        &mut self,
+        token: &Token,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        authorized_id: Option<AccountId>,
+        memo: Option<String>,
+    ) {
+        let mut logs = vec![];
+        self.internal_move_token_logged(token, receiver_id, token_id, authorized_id, memo, &mut logs);
+        EventLog::new(EventLogVariant::NftTransfer(logs)).emit();
+    }
+
+    /// internal_move_token that pushes the NftTransfer logs of the token and its children to logs
+    pub(crate) fn internal_move_token_logged(
+        &mut self,
+        token: &Token,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        authorized_id: Option<AccountId>,
+        memo: Option<String>,
+        logs: &mut Vec<NftTransferLog>,
+    ) {
         assert_ne!(
             &token.owner_id, receiver_id,
             "The token owner and the receiver should be different"
         );
 
-        log!(
-            "Transfer {} from @{} to @{}",
-            token_id,
-            &token.owner_id,
-            receiver_id
-        );
-
-        self.internal_remove_token_from_owner(&token.owner_id, token_id);
-        self.internal_add_token_to_owner(receiver_id, token_id);
+        self.internal_remove_token_from_owner(&token.owner_id, token_id, token.token_type.as_ref());
+        self.internal_add_token_to_owner(receiver_id, token_id, token.token_type.as_ref());
 
         let new_token = Token {
             owner_id: receiver_id.clone(),
@@ -172,11 +349,17 @@ impl Contract {
             token_type: token.token_type.clone(),
         };
         self.tokens_by_id.insert(token_id, &new_token);
+        // CUSTOM - rentals end with a transfer
+        self.internal_clear_user(token_id);
 
-        if let Some(memo) = memo {
-            env::log(format!("Memo: {}", memo).as_bytes());
-        }
+        logs.push(NftTransferLog {
+            authorized_id: authorized_id.clone(),
+            old_owner_id: token.owner_id.clone(),
+            new_owner_id: receiver_id.clone(),
+            token_ids: vec![token_id.clone()],
+            memo,
+        });
 
-        token
+        self.internal_move_children(token_id, receiver_id, authorized_id, logs);
     }
 }
@@ -10,17 +10,53 @@ use near_sdk::{
 };
 
 use crate::internal::*;
+pub use crate::approval::*;
+pub use crate::attributes::*;
+pub use crate::batch::*;
+pub use crate::burn::*;
+pub use crate::composable::*;
+pub use crate::events::*;
+pub use crate::freeze::*;
 pub use crate::metadata::*;
 pub use crate::mint::*;
 pub use crate::nft_core::*;
+pub use crate::operator::*;
+pub use crate::pause::*;
+pub use crate::public_mint::*;
+pub use crate::rental::*;
+pub use crate::roles::*;
+pub use crate::royalty::*;
+pub use crate::series::*;
+pub use crate::storage::*;
 pub use crate::token::*;
+pub use crate::token_type::*;
+pub use crate::upgrade::*;
+pub use crate::validity::*;
 pub use crate::enumerable::*;
 
+mod approval;
+mod attributes;
+mod batch;
+mod burn;
+mod composable;
+mod events;
+mod freeze;
 mod internal;
 mod metadata;
 mod mint;
 mod nft_core;
+mod operator;
+mod pause;
+mod public_mint;
+mod rental;
+mod roles;
+mod royalty;
+mod series;
+mod storage;
 mod token;
+mod token_type;
+mod upgrade;
+mod validity;
 mod enumerable;
 
 // CUSTOM types
@@ -48,10 +84,38 @@ pub struct Contract {
     pub metadata: LazyOption<NFTMetadata>,
 
     /// CUSTOM fields
-    pub supply_cap_by_type: TypeSupplyCaps,
+    pub token_types: UnorderedMap<TokenType, TokenTypeConfig>,
     pub tokens_per_type: LookupMap<TokenType, UnorderedSet<TokenId>>,
-    pub token_types_locked: UnorderedSet<TokenType>,
+    pub expired_tokens: UnorderedSet<TokenId>,
+    pub roles_by_account: UnorderedMap<AccountId, Vec<Role>>,
+    pub minter_token_types: LookupMap<AccountId, Vec<TokenType>>,
+    pub public_mints_per_account: LookupMap<String, u64>,
+    pub mint_ft_token_ids: UnorderedSet<AccountId>,
+    pub beneficiaries: HashMap<AccountId, u32>,
+    pub proceeds: LookupMap<String, Balance>,
     pub contract_royalty: u32,
+    pub series: UnorderedMap<SeriesId, Series>,
+    pub series_by_token: LookupMap<TokenId, SeriesId>,
+    pub royalty_history: LookupMap<TokenId, Vec<RoyaltyChange>>,
+    pub pending_royalty_reassignments: LookupMap<TokenId, HashMap<AccountId, RoyaltyReassignment>>,
+    pub storage_deposits: LookupMap<AccountId, Balance>,
+    pub token_attributes: LookupMap<TokenId, Vec<Attribute>>,
+    pub series_attributes: LookupMap<SeriesId, Vec<Attribute>>,
+    pub tokens_by_trait: LookupMap<String, UnorderedSet<TokenId>>,
+    pub traits_by_group: LookupMap<String, UnorderedSet<String>>,
+    pub children_by_token: LookupMap<TokenId, Vec<ChildToken>>,
+    pub parent_by_child: LookupMap<String, TokenId>,
+    pub token_users: LookupMap<TokenId, TokenUser>,
+    pub approval_conditions: LookupMap<String, ApprovalConditions>,
+    pub operators_by_owner: LookupMap<AccountId, HashMap<AccountId, Option<TokenType>>>,
+    pub paused: Vec<PauseFlag>,
+    pub frozen_tokens: LookupMap<TokenId, FrozenToken>,
+    pub next_token_id: u64,
+    pub tokens_per_owner_type: LookupMap<String, UnorderedSet<TokenId>>,
+    /// next index of token_metadata_by_id to add to tokens_per_owner_type, None when every token is indexed
+    pub owner_type_index_cursor: Option<u64>,
+    pub nest_contract_ids: UnorderedSet<AccountId>,
+    pub mint_storage_by_token: LookupMap<TokenId, MintStorage>,
 }
 
 /// Helper structure to for keys of the persistent collections.
@@ -64,13 +128,42 @@ pub enum StorageKey {
     NftMetadata,
     TokensPerType,
     TokensPerTypeInner { token_type_hash: CryptoHash },
+    /// only read when migrating state from V1, keeps the variants after it stable
     TokenTypesLocked,
+    TokenTypes,
+    ExpiredTokens,
+    RolesByAccount,
+    MinterTokenTypes,
+    PublicMintsPerAccount,
+    MintFTTokenIds,
+    Proceeds,
+    Series,
+    SeriesByToken,
+    RoyaltyHistory,
+    PendingRoyaltyReassignments,
+    StorageDeposits,
+    TokenAttributes,
+    SeriesAttributes,
+    TokensByTrait,
+    TraitsByGroup,
+    TraitsByGroupInner { group_hash: CryptoHash },
+    TokensByTraitInner { trait_hash: CryptoHash },
+    ChildrenByToken,
+    ParentByChild,
+    TokenUsers,
+    ApprovalConditions,
+    OperatorsByOwner,
+    FrozenTokens,
+    TokensPerOwnerType,
+    TokensPerOwnerTypeInner { owner_type_hash: CryptoHash },
+    NestContractIds,
+    MintStorageByToken,
 }
 
 #[near_bindgen]
 impl Contract {
     #[init]
-    pub fn new(owner_id: ValidAccountId, metadata: NFTMetadata, supply_cap_by_type: TypeSupplyCaps, locked: Option<bool>) -> Self {
+    pub fn new(owner_id: ValidAccountId, metadata: NFTMetadata, supply_cap_by_type: TypeSupplyCaps, locked: Option<bool>, unlock_at: Option<U64>) -> Self {
         let mut this = Self {
             tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
             tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
@@ -83,20 +176,46 @@ impl Contract {
                 StorageKey::NftMetadata.try_to_vec().unwrap(),
                 Some(&metadata),
             ),
-            supply_cap_by_type,
+            token_types: UnorderedMap::new(StorageKey::TokenTypes.try_to_vec().unwrap()),
             tokens_per_type: LookupMap::new(StorageKey::TokensPerType.try_to_vec().unwrap()),
-            token_types_locked: UnorderedSet::new(StorageKey::TokenTypesLocked.try_to_vec().unwrap()),
+            expired_tokens: UnorderedSet::new(StorageKey::ExpiredTokens.try_to_vec().unwrap()),
+            roles_by_account: UnorderedMap::new(StorageKey::RolesByAccount.try_to_vec().unwrap()),
+            minter_token_types: LookupMap::new(StorageKey::MinterTokenTypes.try_to_vec().unwrap()),
+            public_mints_per_account: LookupMap::new(StorageKey::PublicMintsPerAccount.try_to_vec().unwrap()),
+            mint_ft_token_ids: UnorderedSet::new(StorageKey::MintFTTokenIds.try_to_vec().unwrap()),
+            beneficiaries: HashMap::new(),
+            proceeds: LookupMap::new(StorageKey::Proceeds.try_to_vec().unwrap()),
             contract_royalty: 0,
+            series: UnorderedMap::new(StorageKey::Series.try_to_vec().unwrap()),
+            series_by_token: LookupMap::new(StorageKey::SeriesByToken.try_to_vec().unwrap()),
+            royalty_history: LookupMap::new(StorageKey::RoyaltyHistory.try_to_vec().unwrap()),
+            pending_royalty_reassignments: LookupMap::new(StorageKey::PendingRoyaltyReassignments.try_to_vec().unwrap()),
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits.try_to_vec().unwrap()),
+            token_attributes: LookupMap::new(StorageKey::TokenAttributes.try_to_vec().unwrap()),
+            series_attributes: LookupMap::new(StorageKey::SeriesAttributes.try_to_vec().unwrap()),
+            tokens_by_trait: LookupMap::new(StorageKey::TokensByTrait.try_to_vec().unwrap()),
+            traits_by_group: LookupMap::new(StorageKey::TraitsByGroup.try_to_vec().unwrap()),
+            children_by_token: LookupMap::new(StorageKey::ChildrenByToken.try_to_vec().unwrap()),
+            parent_by_child: LookupMap::new(StorageKey::ParentByChild.try_to_vec().unwrap()),
+            token_users: LookupMap::new(StorageKey::TokenUsers.try_to_vec().unwrap()),
+            approval_conditions: LookupMap::new(StorageKey::ApprovalConditions.try_to_vec().unwrap()),
+            operators_by_owner: LookupMap::new(StorageKey::OperatorsByOwner.try_to_vec().unwrap()),
+            paused: vec![],
+            frozen_tokens: LookupMap::new(StorageKey::FrozenTokens.try_to_vec().unwrap()),
+            next_token_id: 1,
+            tokens_per_owner_type: LookupMap::new(StorageKey::TokensPerOwnerType.try_to_vec().unwrap()),
+            owner_type_index_cursor: None,
+            nest_contract_ids: UnorderedSet::new(StorageKey::NestContractIds.try_to_vec().unwrap()),
+            mint_storage_by_token: LookupMap::new(StorageKey::MintStorageByToken.try_to_vec().unwrap()),
         };
 
-        if locked.unwrap_or(false) {
-            // CUSTOM - tokens are locked by default
-            for token_type in this.supply_cap_by_type.keys() {
-                this.token_types_locked.insert(&token_type);
-            }
+        // CUSTOM - tokens are locked by default if locked: true
+        for (token_type, hard_cap) in supply_cap_by_type {
+            this.token_types.insert(&token_type, &TokenTypeConfig::new(hard_cap, locked.unwrap_or(false), unlock_at));
         }
 
         this.measure_min_token_storage_cost();
+        write_state_version();
 
         this
     }
@@ -122,27 +241,31 @@ impl Contract {
         self.tokens_per_owner.remove(&tmp_account_id);
     }
 
-    /// CUSTOM - setters for owner
+    /// CUSTOM - setters gated by role, see roles.rs
 
     pub fn set_contract_royalty(&mut self, contract_royalty: u32) {
-        self.assert_owner();
+        self.assert_role(Role::Admin);
         assert!(contract_royalty <= CONTRACT_ROYALTY_CAP, "Contract royalties limited to 10% for owner");
         self.contract_royalty = contract_royalty;
     }
 
-    pub fn add_token_types(&mut self, supply_cap_by_type: TypeSupplyCaps, locked: Option<bool>) {
-        self.assert_owner();
-        for (token_type, hard_cap) in &supply_cap_by_type {
-            if locked.unwrap_or(false) {
-                assert!(self.token_types_locked.insert(&token_type), "Token type should not be locked");
-            }
-            assert!(self.supply_cap_by_type.insert(token_type.to_string(), *hard_cap).is_none(), "Token type exists");
+    pub fn add_token_types(&mut self, supply_cap_by_type: TypeSupplyCaps, locked: Option<bool>, unlock_at: Option<U64>) {
+        self.assert_role(Role::TypeManager);
+        for (token_type, hard_cap) in supply_cap_by_type {
+            assert!(self.series.get(&token_type).is_none(), "Token type is a series_id");
+            assert!(
+                self.token_types.insert(&token_type, &TokenTypeConfig::new(hard_cap, locked.unwrap_or(false), unlock_at)).is_none(),
+                "Token type exists"
+            );
         }
     }
 
     pub fn unlock_token_types(&mut self, token_types: Vec<String>) {
+        self.assert_role(Role::TypeManager);
         for token_type in &token_types {
-            self.token_types_locked.remove(&token_type);
+            let mut config = self.internal_token_type(token_type);
+            config.locked = false;
+            self.token_types.insert(token_type, &config);
         }
     }
 
@@ -153,17 +276,406 @@ impl Contract {
     }
 
     pub fn get_supply_caps(&self) -> TypeSupplyCaps {
-        self.supply_cap_by_type.clone()
+        self.token_types.iter().map(|(token_type, config)| (token_type, config.cap)).collect()
     }
 
     pub fn get_token_types_locked(&self) -> Vec<String> {
-        self.token_types_locked.to_vec()
+        self.token_types.iter().filter(|(_, config)| config.is_locked()).map(|(token_type, _)| token_type).collect()
     }
 
     pub fn is_token_locked(&self, token_id: TokenId) -> bool {
         let token = self.tokens_by_id.get(&token_id).expect("No token");
         assert!(token.token_type.is_some(), "Token must have type");
-        let token_type = token.token_type.unwrap();
-        self.token_types_locked.contains(&token_type)
+        self.internal_is_type_locked(&token.token_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::MockedBlockchain;
+    use near_sdk::{testing_env, VMContext};
+
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn owner() -> ValidAccountId {
+        ValidAccountId::try_from("owner.near").unwrap()
+    }
+    fn alice() -> ValidAccountId {
+        ValidAccountId::try_from("alice.near").unwrap()
+    }
+
+    fn get_context(predecessor_account_id: AccountId, attached_deposit: Balance) -> VMContext {
+        VMContext {
+            current_account_id: "nft.near".to_string(),
+            signer_account_id: predecessor_account_id.clone(),
+            signer_account_pk: vec![0, 1, 2],
+            predecessor_account_id,
+            input: vec![],
+            block_index: 0,
+            block_timestamp: 0,
+            account_balance: 1000 * 10u128.pow(24),
+            account_locked_balance: 0,
+            storage_usage: 10u64.pow(6),
+            attached_deposit,
+            prepaid_gas: 10u64.pow(18),
+            random_seed: vec![0, 1, 2],
+            is_view: false,
+            output_data_receivers: vec![],
+            epoch_height: 0,
+        }
+    }
+
+    fn new_contract() -> Contract {
+        testing_env!(get_context(owner().into(), 0));
+        let mut supply_cap_by_type = HashMap::new();
+        supply_cap_by_type.insert("typeA".to_string(), U64(100));
+        Contract::new(
+            owner(),
+            NFTMetadata {
+                spec: "nft-1".to_string(),
+                name: "Test NFT".to_string(),
+                symbol: "TNFT".to_string(),
+                icon: None,
+                base_uri: None,
+                reference: None,
+                reference_hash: None,
+            },
+            supply_cap_by_type,
+            None,
+            None,
+        )
+    }
+
+    fn metadata(title: Option<&str>) -> TokenMetadata {
+        TokenMetadata {
+            title: title.map(|title| title.to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    /// the owner mints token_id to receiver_id
+    fn mint(contract: &mut Contract, token_id: &str, receiver_id: ValidAccountId, token_type: Option<&str>) {
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        contract.nft_mint(
+            Some(token_id.to_string()),
+            metadata(None),
+            None,
+            Some(receiver_id),
+            token_type.map(|token_type| token_type.to_string()),
+        );
+    }
+
+    fn bob() -> ValidAccountId {
+        ValidAccountId::try_from("bob.near").unwrap()
+    }
+
+    fn owner_of(contract: &Contract, token_id: &str) -> AccountId {
+        contract.nft_token(token_id.to_string()).unwrap().owner_id
+    }
+
+    #[test]
+    fn series_editions_merge_the_series_metadata() {
+        let mut contract = new_contract();
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        contract.nft_create_series("art".to_string(), metadata(Some("Art")), Some(3), None, None);
+        // an explicit mint takes the id of the first edition
+        mint(&mut contract, "art:1", alice(), None);
+
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        let token_id = contract.nft_mint_edition("art".to_string(), None, Some(alice()));
+        assert_eq!(token_id, "art:2");
+        let token = contract.nft_token(token_id.clone()).unwrap();
+        assert_eq!(token.owner_id, "alice.near");
+        assert_eq!(token.metadata.title, Some("Art".to_string()));
+        assert_eq!(token.metadata.copies, Some(3));
+        assert_eq!(contract.get_token_series(token_id), Some("art".to_string()));
+
+        let token_id = contract.nft_mint_edition("art".to_string(), Some(metadata(Some("Art #3"))), None);
+        assert_eq!(token_id, "art:3");
+        assert_eq!(contract.nft_token(token_id).unwrap().metadata.title, Some("Art #3".to_string()));
+        assert_eq!(contract.get_series("art".to_string()).unwrap().series.minted, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "All editions are minted")]
+    fn series_copies_cap_editions() {
+        let mut contract = new_contract();
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        contract.nft_create_series("art".to_string(), metadata(Some("Art")), Some(1), None, None);
+        contract.nft_mint_edition("art".to_string(), None, None);
+        contract.nft_mint_edition("art".to_string(), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the creator can mint editions")]
+    fn series_editions_are_minted_by_the_creator() {
+        let mut contract = new_contract();
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        contract.nft_create_series("art".to_string(), metadata(Some("Art")), None, None, None);
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_mint_edition("art".to_string(), None, None);
+    }
+
+    #[test]
+    fn royalty_holders_transfer_part_of_their_royalty() {
+        let mut contract = new_contract();
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        let mut perpetual_royalties = HashMap::new();
+        perpetual_royalties.insert(alice().into(), 1000);
+        contract.nft_mint(Some("1".to_string()), metadata(None), Some(perpetual_royalties), None, None);
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_transfer_royalty("1".to_string(), bob(), Some(400));
+        let royalty = contract.nft_token("1".to_string()).unwrap().royalty;
+        assert_eq!(royalty.get("alice.near"), Some(&600));
+        assert_eq!(royalty.get("bob.near"), Some(&400));
+
+        // the rest by default, alice is no longer a recipient
+        contract.nft_transfer_royalty("1".to_string(), bob(), None);
+        let royalty = contract.nft_token("1".to_string()).unwrap().royalty;
+        assert_eq!(royalty.get("alice.near"), None);
+        assert_eq!(royalty.get("bob.near"), Some(&1000));
+
+        let history = contract.get_royalty_history("1".to_string());
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].amount, 600);
+        assert_eq!(history[1].authorized_id, "alice.near");
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount must be between 1 and 1000")]
+    fn royalty_transfers_are_bounded_by_the_holding() {
+        let mut contract = new_contract();
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        let mut perpetual_royalties = HashMap::new();
+        perpetual_royalties.insert(alice().into(), 1000);
+        contract.nft_mint(Some("1".to_string()), metadata(None), Some(perpetual_royalties), None, None);
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_transfer_royalty("1".to_string(), bob(), Some(1001));
+    }
+
+    #[test]
+    fn operators_transfer_every_token_of_the_owner() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+        mint(&mut contract, "2", alice(), Some("typeA"));
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_approve_all(bob(), None);
+        assert!(contract.nft_is_operator(alice().into(), bob().into(), Some("2".to_string())));
+
+        // no token approval, the approval_id is ignored
+        testing_env!(get_context(bob().into(), 1));
+        contract.nft_transfer(owner(), "1".to_string(), 0, None);
+        contract.nft_transfer(owner(), "2".to_string(), 0, None);
+        assert_eq!(owner_of(&contract, "1"), "owner.near");
+        assert_eq!(owner_of(&contract, "2"), "owner.near");
+        // operators stay after the transfers
+        assert_eq!(contract.nft_operators(alice().into()).len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn operators_of_a_token_type_only_transfer_that_type() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+        mint(&mut contract, "2", alice(), Some("typeA"));
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_approve_all(bob(), Some("typeA".to_string()));
+        assert!(contract.nft_is_operator(alice().into(), bob().into(), Some("2".to_string())));
+        assert!(!contract.nft_is_operator(alice().into(), bob().into(), Some("1".to_string())));
+
+        testing_env!(get_context(bob().into(), 1));
+        contract.nft_transfer(owner(), "2".to_string(), 0, None);
+        contract.nft_transfer(owner(), "1".to_string(), 0, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn revoked_operators_cannot_transfer() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_approve_all(bob(), None);
+        testing_env!(get_context(alice().into(), 1));
+        contract.nft_revoke_operator(bob());
+        assert!(!contract.nft_is_operator(alice().into(), bob().into(), None));
+
+        testing_env!(get_context(bob().into(), 1));
+        contract.nft_transfer(owner(), "1".to_string(), 0, None);
+    }
+
+    #[test]
+    fn batch_transfers_move_every_token() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+        mint(&mut contract, "2", alice(), None);
+        mint(&mut contract, "3", owner(), None);
+
+        // approved for "3", owner of "1" and "2"
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        contract.nft_approve("3".to_string(), alice(), None, None, None);
+        testing_env!(get_context(alice().into(), 1));
+        contract.nft_batch_transfer(vec![
+            (bob(), "1".to_string(), None, None),
+            (owner(), "2".to_string(), None, Some("memo".to_string())),
+            (bob(), "3".to_string(), Some(0), None),
+        ]);
+        assert_eq!(owner_of(&contract, "1"), "bob.near");
+        assert_eq!(owner_of(&contract, "2"), "owner.near");
+        assert_eq!(owner_of(&contract, "3"), "bob.near");
+        assert!(contract.nft_token("3".to_string()).unwrap().approved_account_ids.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate token 1")]
+    fn batch_transfers_reject_duplicates() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+
+        testing_env!(get_context(alice().into(), 1));
+        contract.nft_batch_transfer(vec![
+            (bob(), "1".to_string(), None, None),
+            (owner(), "1".to_string(), None, None),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized")]
+    fn batch_transfers_are_all_or_nothing() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+        mint(&mut contract, "2", bob(), None);
+
+        testing_env!(get_context(alice().into(), 1));
+        contract.nft_batch_transfer(vec![
+            (owner(), "1".to_string(), None, None),
+            (owner(), "2".to_string(), None, None),
+        ]);
+    }
+
+    #[test]
+    fn nested_tokens_move_with_their_parent() {
+        let mut contract = new_contract();
+        mint(&mut contract, "parent", alice(), None);
+        mint(&mut contract, "child", alice(), None);
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_nest("child".to_string(), "parent".to_string());
+        assert_eq!(contract.nft_parent("child".to_string(), None), Some("parent".to_string()));
+
+        testing_env!(get_context(alice().into(), 1));
+        contract.nft_transfer(bob(), "parent".to_string(), 0, None);
+        assert_eq!(owner_of(&contract, "child"), "bob.near");
+        assert_eq!(contract.nft_root_owner("child".to_string(), None), Some("bob.near".to_string()));
+
+        // the root owner detaches the child to any receiver
+        testing_env!(get_context(bob().into(), 1));
+        contract.nft_detach(
+            "parent".to_string(),
+            ChildToken { contract_id: "nft.near".to_string(), token_id: "child".to_string() },
+            Some(owner()),
+        );
+        assert_eq!(owner_of(&contract, "child"), "owner.near");
+        assert!(contract.nft_children("parent".to_string()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Token is nested, detach it first")]
+    fn nested_tokens_cannot_be_transferred_alone() {
+        let mut contract = new_contract();
+        mint(&mut contract, "parent", alice(), None);
+        mint(&mut contract, "child", alice(), None);
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_nest("child".to_string(), "parent".to_string());
+        testing_env!(get_context(alice().into(), 1));
+        contract.nft_transfer(bob(), "child".to_string(), 0, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the holder can transfer tokens of this type")]
+    fn nested_tokens_keep_the_transfer_policy_of_their_type() {
+        let mut contract = new_contract();
+        mint(&mut contract, "parent", alice(), None);
+        mint(&mut contract, "child", alice(), Some("typeA"));
+
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        contract.nft_nest("child".to_string(), "parent".to_string());
+        contract.nft_approve_all(bob(), None);
+        testing_env!(get_context(owner().into(), 0));
+        contract.set_token_type_transfer_policy("typeA".to_string(), TransferPolicy::HolderOnly);
+
+        // bob can move the parent, but not the child of a holder only type along with it
+        testing_env!(get_context(bob().into(), 1));
+        contract.nft_transfer(bob(), "parent".to_string(), 0, None);
+    }
+
+    #[test]
+    fn revoke_all_notifies_while_gas_lasts() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+        testing_env!(get_context(alice().into(), 10u128.pow(24)));
+        for i in 0..20 {
+            let account_id = ValidAccountId::try_from(format!("market{}.near", i)).unwrap();
+            contract.nft_approve("1".to_string(), account_id, None, None, None);
+        }
+
+        let mut context = get_context(alice().into(), 1);
+        context.prepaid_gas = 100_000_000_000_000;
+        testing_env!(context);
+        contract.nft_revoke_all("1".to_string());
+        assert!(contract.nft_token("1".to_string()).unwrap().approved_account_ids.is_empty());
+        let receipts = near_sdk::serde_json::to_string(&near_sdk::test_utils::get_created_receipts()).unwrap();
+        let notified = receipts.matches("nft_on_revoke").count();
+        assert!(notified > 0 && notified < 10, "notified {}", notified);
+    }
+
+    fn attribute(trait_type: &str, value: &str) -> Attribute {
+        Attribute { trait_type: trait_type.to_string(), value: value.to_string() }
+    }
+
+    #[test]
+    #[should_panic(expected = "Only tokens with a token_type or series can have attributes")]
+    fn attributes_need_a_token_type_or_series() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), None);
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        contract.nft_set_attributes("1".to_string(), vec![attribute("background", "gold")]);
+    }
+
+    #[test]
+    fn trait_counts_follow_attribute_changes() {
+        let mut contract = new_contract();
+        mint(&mut contract, "1", alice(), Some("typeA"));
+        mint(&mut contract, "2", alice(), Some("typeA"));
+        testing_env!(get_context(owner().into(), 10u128.pow(24)));
+        contract.nft_set_attributes("1".to_string(), vec![attribute("background", "gold")]);
+        contract.nft_set_attributes("2".to_string(), vec![attribute("background", "gold")]);
+        contract.nft_set_attributes("2".to_string(), vec![attribute("background", "blue")]);
+
+        let counts = contract.get_trait_counts("typeA".to_string(), None, None);
+        assert_eq!(counts.len(), 2);
+        assert!(counts.iter().all(|count| count.trait_type == "background" && count.count.0 == 1));
+        assert_eq!(contract.nft_rarity_score("1".to_string()).0, 2 * RARITY_SCORE_SCALE);
+
+        contract.nft_set_attributes("2".to_string(), vec![]);
+        let counts = contract.get_trait_counts("typeA".to_string(), None, None);
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].value, "gold");
     }
 }
@@ -1,5 +1,23 @@
 use crate::*;
 
+/// CUSTOM - who paid for the storage of a token at mint, refunded to them when it's burned
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MintStorage {
+    pub payer_id: AccountId,
+    pub storage_used: StorageUsage,
+}
+
+/// CUSTOM - one entry of nft_batch_mint, same arguments as nft_mint
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintArgs {
+    pub token_id: Option<TokenId>,
+    pub metadata: TokenMetadata,
+    pub perpetual_royalties: Option<HashMap<AccountId, u32>>,
+    pub receiver_id: Option<ValidAccountId>,
+    pub token_type: Option<TokenType>,
+}
+
 #[near_bindgen]
 impl Contract {
     #[payable]
@@ -11,37 +29,83 @@ impl Contract {
         receiver_id: Option<ValidAccountId>,
         token_type: Option<TokenType>,
     ) {
+        self.assert_minter(&token_type);
+        let initial_storage_usage = env::storage_usage();
+        let owner_id: AccountId = receiver_id.map(|a| a.into()).unwrap_or_else(env::predecessor_account_id);
 
-        let mut final_token_id = format!("{}", self.token_metadata_by_id.len() + 1);
-        if let Some(token_id) = token_id {
-            final_token_id = token_id
-        }
+        let final_token_id = self.internal_mint(token_id, metadata, perpetual_royalties, &owner_id, token_type);
+        let required_storage_in_bytes =
+            self.internal_record_mint_storage(&final_token_id, env::predecessor_account_id(), initial_storage_usage);
 
-        let initial_storage_usage = env::storage_usage();
-        let mut owner_id = env::predecessor_account_id();
-        if let Some(receiver_id) = receiver_id {
-            owner_id = receiver_id.into();
-        }
+        EventLog::new(EventLogVariant::NftMint(vec![NftMintLog {
+            owner_id,
+            token_ids: vec![final_token_id],
+            memo: None,
+        }])).emit();
 
-        // CUSTOM - create royalty map
-        let mut royalty = HashMap::new();
-        let mut total_perpetual = 0;
-        // user added perpetual_royalties (percentage paid with every transfer)
-        if let Some(perpetual_royalties) = perpetual_royalties {
-            assert!(perpetual_royalties.len() < 7, "Cannot add more than 6 perpetual royalty amounts");
-            for (account, amount) in perpetual_royalties {
-                royalty.insert(account, amount);
-                total_perpetual += amount;
-            }
+        self.internal_charge_storage(required_storage_in_bytes);
+    }
+
+    /// CUSTOM - mint many tokens in one call, all or nothing
+    /// supply caps are enforced across the whole batch and storage is charged once
+    #[payable]
+    pub fn nft_batch_mint(&mut self, tokens: Vec<MintArgs>) -> Vec<TokenId> {
+        assert!(!tokens.is_empty(), "Nothing to mint");
+        let predecessor_account_id = env::predecessor_account_id();
+
+        let mut required_storage_in_bytes = 0;
+        let mut token_ids = vec![];
+        let mut token_ids_by_owner: HashMap<AccountId, Vec<TokenId>> = HashMap::new();
+        for MintArgs { token_id, metadata, perpetual_royalties, receiver_id, token_type } in tokens {
+            self.assert_minter(&token_type);
+            let initial_storage_usage = env::storage_usage();
+            let owner_id: AccountId = receiver_id.map(|a| a.into()).unwrap_or_else(|| predecessor_account_id.clone());
+            let final_token_id = self.internal_mint(token_id, metadata, perpetual_royalties, &owner_id, token_type);
+            required_storage_in_bytes +=
+                self.internal_record_mint_storage(&final_token_id, predecessor_account_id.clone(), initial_storage_usage);
+            token_ids_by_owner.entry(owner_id).or_insert_with(Vec::new).push(final_token_id.clone());
+            token_ids.push(final_token_id);
         }
-        // royalty limit for minter capped at 20%
-        assert!(total_perpetual <= MINTER_ROYALTY_CAP, "Perpetual royalties cannot be more than 20%");
 
-        // CUSTOM - enforce minting caps by token_type 
-        if token_type.is_some() {
+        EventLog::new(EventLogVariant::NftMint(
+            token_ids_by_owner.into_iter().map(|(owner_id, token_ids)| NftMintLog {
+                owner_id,
+                token_ids,
+                memo: None,
+            }).collect()
+        )).emit();
+
+        self.internal_charge_storage(required_storage_in_bytes);
+
+        token_ids
+    }
+}
+
+impl Contract {
+    /// creates the token and all of its indexes, callers handle storage and events
+    pub(crate) fn internal_mint(
+        &mut self,
+        token_id: Option<TokenId>,
+        metadata: TokenMetadata,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        owner_id: &AccountId,
+        token_type: Option<TokenType>,
+    ) -> TokenId {
+        self.assert_not_paused(PauseFlag::Minting);
+        let final_token_id = token_id.unwrap_or_else(|| {
+            let next_token_id = self.internal_next_token_id();
+            self.next_token_id = next_token_id + 1;
+            next_token_id.to_string()
+        });
+
+        let royalty = self.internal_mint_royalty(&token_type, perpetual_royalties);
+
+        // CUSTOM - enforce minting caps by token_type
+        let config = token_type.as_ref().map(|token_type| self.internal_token_type(token_type));
+        if let Some(config) = config {
             let token_type = token_type.clone().unwrap();
-            let cap = u64::from(*self.supply_cap_by_type.get(&token_type).expect("Token type must have supply cap."));
-            let supply = u64::from(self.nft_supply_for_type(&token_type));
+            let cap = u64::from(config.cap);
+            let supply = self.internal_supply_for_type(&token_type);
             assert!(supply < cap, "Cannot mint anymore of token type.");
             let mut tokens_per_type = self
                 .tokens_per_type
@@ -61,7 +125,7 @@ impl Contract {
         // END CUSTOM
 
         let token = Token {
-            owner_id,
+            owner_id: owner_id.clone(),
             approved_account_ids: Default::default(),
             next_approval_id: 0,
             royalty,
@@ -72,12 +136,48 @@ impl Contract {
             "Token already exists"
         );
         self.token_metadata_by_id.insert(&final_token_id, &metadata);
-        self.internal_add_token_to_owner(&token.owner_id, &final_token_id);
+        self.internal_add_token_to_owner(&token.owner_id, &final_token_id, token.token_type.as_ref());
 
-        let new_token_size_in_bytes = env::storage_usage() - initial_storage_usage;
-        let required_storage_in_bytes =
-            self.extra_storage_in_bytes_per_token + new_token_size_in_bytes;
+        final_token_id
+    }
+
+    /// CUSTOM - records payer_id as the payer of a token minted since initial_storage_usage
+    /// returns the bytes to charge them, including the record and extra_storage_in_bytes_per_token
+    pub(crate) fn internal_record_mint_storage(
+        &mut self,
+        token_id: &TokenId,
+        payer_id: AccountId,
+        initial_storage_usage: StorageUsage,
+    ) -> StorageUsage {
+        // storage_used has a fixed size, insert the record before measuring so it's counted
+        let mut mint_storage = MintStorage { payer_id, storage_used: 0 };
+        self.mint_storage_by_token.insert(token_id, &mint_storage);
+        mint_storage.storage_used = self.extra_storage_in_bytes_per_token + env::storage_usage() - initial_storage_usage;
+        self.mint_storage_by_token.insert(token_id, &mint_storage);
+        mint_storage.storage_used
+    }
 
-        refund_deposit(required_storage_in_bytes);
+    /// CUSTOM - royalty map of a new token, starting from the royalties of the token_type
+    pub(crate) fn internal_mint_royalty(
+        &self,
+        token_type: &Option<TokenType>,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+    ) -> HashMap<AccountId, u32> {
+        let mut royalty = token_type.as_ref()
+            .map(|token_type| self.internal_token_type(token_type).royalty)
+            .unwrap_or_default();
+        let mut total_perpetual = royalty.values().sum::<u32>();
+        // user added perpetual_royalties (percentage paid with every transfer)
+        if let Some(perpetual_royalties) = perpetual_royalties {
+            assert!(perpetual_royalties.len() < 7, "Cannot add more than 6 perpetual royalty amounts");
+            for (account, amount) in perpetual_royalties {
+                total_perpetual += amount;
+                *royalty.entry(account).or_insert(0) += amount;
+            }
+            assert!(royalty.len() < 7, "Cannot add more than 6 perpetual royalty amounts");
+        }
+        // royalty limit for minter capped at 20%
+        assert!(total_perpetual <= MINTER_ROYALTY_CAP, "Perpetual royalties cannot be more than 20%");
+        royalty
     }
-}
\ No newline at end of file
+}
@@ -1,8 +1,12 @@
 use crate::*;
 use near_sdk::json_types::{ValidAccountId};
-use near_sdk::{ext_contract, log, Gas, PromiseResult};
+use near_sdk::{ext_contract, Gas, PromiseResult};
 
 const GAS_FOR_NFT_APPROVE: Gas = 10_000_000_000_000;
+/// best-effort gas budget for each nft_on_revoke notification
+const GAS_FOR_NFT_ON_REVOKE: Gas = 10_000_000_000_000;
+/// gas nft_revoke_all keeps for creating the next notification and saving the token
+const GAS_FOR_REVOKE_ALL: Gas = 10_000_000_000_000;
 const GAS_FOR_RESOLVE_TRANSFER: Gas = 10_000_000_000_000;
 const GAS_FOR_NFT_TRANSFER_CALL: Gas = 25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER;
 const NO_DEPOSIT: Balance = 0;
@@ -38,13 +42,23 @@ pub trait NonFungibleTokenCore {
         msg: String,
     ) -> PromiseOrValue<bool>;
 
-    fn nft_approve(&mut self, token_id: TokenId, account_id: ValidAccountId, msg: Option<String>);
+    /// CUSTOM - expires_at (ms) and min_price are optional conditions of the approval
+    fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: ValidAccountId,
+        msg: Option<String>,
+        expires_at: Option<U64>,
+        min_price: Option<U128>,
+    );
 
+    /// CUSTOM - false once the approval expired, or if balance is below its min_price
 	fn nft_is_approved(
         &self,
         token_id: TokenId,
         approved_account_id: AccountId,
         approval_id: Option<u64>,
+        balance: Option<U128>,
     ) -> bool;
 
     fn nft_revoke(&mut self, token_id: TokenId, account_id: ValidAccountId);
@@ -79,7 +93,11 @@ trait NonFungibleTokenApprovalsReceiver {
     );
 }
 
-// TODO: create nft_on_revoke
+#[ext_contract(ext_non_fungible_revoke_receiver)]
+trait NonFungibleTokenRevokeReceiver {
+    /// Notifies a previously approved account (e.g. a market) that its approval was revoked
+    fn nft_on_revoke(&mut self, token_id: TokenId);
+}
 
 #[ext_contract(ext_self)]
 trait NonFungibleTokenResolver {
@@ -102,6 +120,16 @@ trait NonFungibleTokenResolver {
     ) -> bool;
 }
 
+/// best-effort, the promise is not returned so a failing receiver can't revert the revoke
+fn notify_revoked_account_id(token_id: &TokenId, account_id: &AccountId) {
+    ext_non_fungible_revoke_receiver::nft_on_revoke(
+        token_id.clone(),
+        account_id,
+        NO_DEPOSIT,
+        GAS_FOR_NFT_ON_REVOKE,
+    );
+}
+
 #[near_bindgen]
 impl NonFungibleTokenCore for Contract {
 
@@ -121,45 +149,20 @@ impl NonFungibleTokenCore for Contract {
             &token_id,
             Some(approval_id),
             memo,
+            None,
         );
         refund_approved_account_ids(
             previous_token.owner_id.clone(),
             &previous_token.approved_account_ids,
         );
+        self.internal_remove_approval_conditions(&token_id, &previous_token.owner_id, previous_token.approved_account_ids.keys());
     }
 
     fn nft_payout(&self, token_id: String, balance: U128, max_len_payout: u32) -> Payout {
-		let token = self.tokens_by_id.get(&token_id).expect("No token");
-
-        // compute payouts based on balance option
-        // adds in contract_royalty and computes previous owner royalty from remainder
-        let owner_id = token.owner_id;
-        let mut total_perpetual = 0;
-        let balance_u128 = u128::from(balance);
-		let mut payout: Payout = HashMap::new();
-		let royalty = token.royalty;
-
-		assert!(royalty.len() as u32 <= max_len_payout, "Market cannot payout to that many receivers");
-
-		for (k, v) in royalty.iter() {
-			let key = k.clone();
-			if key != owner_id {
-				payout.insert(key, royalty_to_payout(*v, balance_u128));
-				total_perpetual += *v;
-			}
-		}
-		
-		// payout to contract owner - may be previous token owner, they get remainder of balance
-		if self.contract_royalty > 0 && self.owner_id != owner_id {
-			payout.insert(self.owner_id.clone(), royalty_to_payout(self.contract_royalty, balance_u128));
-			total_perpetual += self.contract_royalty;
-		}
-		assert!(total_perpetual <= MINTER_ROYALTY_CAP + CONTRACT_ROYALTY_CAP, "Royalties should not be more than caps");
-		// payout to previous owner
-		payout.insert(owner_id, royalty_to_payout(10000 - total_perpetual, balance_u128));
-
-		payout
-	}
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        self.assert_token_valid(&token_id, &token);
+        self.internal_payout(&token, balance.0, max_len_payout)
+    }
 
     #[payable]
     fn nft_transfer_payout(
@@ -173,46 +176,24 @@ impl NonFungibleTokenCore for Contract {
     ) -> Payout {
         assert_one_yocto();
         let sender_id = env::predecessor_account_id();
+        // CUSTOM - soulbound tokens can't be sold
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        assert!(!self.internal_is_non_transferable(&token.token_type), "Tokens of this type are non-transferable");
         let previous_token = self.internal_transfer(
             &sender_id,
             receiver_id.as_ref(),
             &token_id,
             Some(approval_id),
             Some(memo),
+            Some(balance.0),
         );
         refund_approved_account_ids(
             previous_token.owner_id.clone(),
             &previous_token.approved_account_ids,
         );
+        self.internal_remove_approval_conditions(&token_id, &previous_token.owner_id, previous_token.approved_account_ids.keys());
 
-        // compute payouts based on balance option
-        // adds in contract_royalty and computes previous owner royalty from remainder
-        let owner_id = previous_token.owner_id;
-        let mut total_perpetual = 0;
-        let balance_u128 = u128::from(balance);
-		let mut payout: Payout = HashMap::new();
-		let royalty = self.tokens_by_id.get(&token_id).expect("No token").royalty;
-
-		assert!(royalty.len() as u32 <= max_len_payout, "Market cannot payout to that many receivers");
-
-		for (k, v) in royalty.iter() {
-			let key = k.clone();
-			if key != owner_id {
-				payout.insert(key, royalty_to_payout(*v, balance_u128));
-				total_perpetual += *v;
-			}
-		}
-		
-		// payout to contract owner - may be previous token owner, they get remainder of balance
-		if self.contract_royalty > 0 && self.owner_id != owner_id {
-			payout.insert(self.owner_id.clone(), royalty_to_payout(self.contract_royalty, balance_u128));
-			total_perpetual += self.contract_royalty;
-		}
-		assert!(total_perpetual <= MINTER_ROYALTY_CAP + CONTRACT_ROYALTY_CAP, "Royalties should not be more than caps");
-		// payout to previous owner
-		payout.insert(owner_id, royalty_to_payout(10000 - total_perpetual, balance_u128));
-
-		payout
+        self.internal_payout(&previous_token, balance.0, max_len_payout)
     }
 
     #[payable]
@@ -232,6 +213,7 @@ impl NonFungibleTokenCore for Contract {
             &token_id,
             Some(approval_id),
             memo,
+            None,
         );
         // Initiating receiver's call and the callback
         ext_non_fungible_token_receiver::nft_on_transfer(
@@ -255,7 +237,14 @@ impl NonFungibleTokenCore for Contract {
     }
 
     #[payable]
-    fn nft_approve(&mut self, token_id: TokenId, account_id: ValidAccountId, msg: Option<String>) {
+    fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: ValidAccountId,
+        msg: Option<String>,
+        expires_at: Option<U64>,
+        min_price: Option<U128>,
+    ) {
         assert_at_least_one_yocto();
         let account_id: AccountId = account_id.into();
 
@@ -266,6 +255,18 @@ impl NonFungibleTokenCore for Contract {
             &token.owner_id,
             "Predecessor must be the token owner."
         );
+        self.assert_token_valid(&token_id, &token);
+        self.assert_not_nested(&token_id);
+        self.assert_not_frozen(&token_id);
+        if let Some(token_type) = token.token_type.as_ref() {
+            assert!(
+                !matches!(
+                    self.internal_token_type(token_type).transfer_policy,
+                    TransferPolicy::HolderOnly | TransferPolicy::NonTransferable
+                ),
+                "Approvals are disabled for this token type"
+            );
+        }
 
         let approval_id: u64 = token.next_approval_id;
         let is_new_approval = token
@@ -282,7 +283,12 @@ impl NonFungibleTokenCore for Contract {
         token.next_approval_id += 1;
         self.tokens_by_id.insert(&token_id, &token);
 
-        refund_deposit(storage_used);
+        // CUSTOM - conditions are paid for like the approval
+        let initial_storage_usage = env::storage_usage();
+        self.internal_set_approval_conditions(&token_id, &account_id, approval_id, expires_at, min_price);
+        let storage_used = storage_used + env::storage_usage().saturating_sub(initial_storage_usage);
+
+        self.internal_charge_storage(storage_used);
 
         if let Some(msg) = msg {
             
@@ -311,10 +317,11 @@ impl NonFungibleTokenCore for Contract {
         token_id: TokenId,
         approved_account_id: AccountId,
         approval_id: Option<u64>,
+        balance: Option<U128>,
     ) -> bool {
         let token = self.tokens_by_id.get(&token_id).expect("No token");
 		let approval = token.approved_account_ids.get(&approved_account_id);
-		if let Some(approval) = approval {
+		let is_approved = if let Some(approval) = approval {
 			if let Some(approval_id) = approval_id {
 				approval_id == *approval
 			} else {
@@ -322,7 +329,16 @@ impl NonFungibleTokenCore for Contract {
 			}
 		} else {
 			false
-		}
+		};
+		// CUSTOM - conditions of the approval
+		is_approved && self.internal_approval_conditions(&token_id, &token, &approved_account_id)
+			.map(|conditions| {
+				!conditions.is_expired() && match (conditions.min_price, balance) {
+					(Some(min_price), Some(balance)) => balance.0 >= min_price.0,
+					_ => true,
+				}
+			})
+			.unwrap_or(true)
     }
 
     #[payable]
@@ -336,8 +352,11 @@ impl NonFungibleTokenCore for Contract {
             .remove(account_id.as_ref())
             .is_some()
         {
-            refund_approved_account_ids_iter(predecessor_account_id, [account_id.into()].iter());
+            let account_id: AccountId = account_id.into();
+            refund_approved_account_ids_iter(predecessor_account_id.clone(), [account_id.clone()].iter());
             self.tokens_by_id.insert(&token_id, &token);
+            self.internal_remove_approval_conditions(&token_id, &predecessor_account_id, [account_id.clone()].iter());
+            notify_revoked_account_id(&token_id, &account_id);
         }
     }
 
@@ -348,7 +367,16 @@ impl NonFungibleTokenCore for Contract {
         let predecessor_account_id = env::predecessor_account_id();
         assert_eq!(&predecessor_account_id, &token.owner_id);
         if !token.approved_account_ids.is_empty() {
-            refund_approved_account_ids(predecessor_account_id, &token.approved_account_ids);
+            refund_approved_account_ids(predecessor_account_id.clone(), &token.approved_account_ids);
+            self.internal_remove_approval_conditions(&token_id, &predecessor_account_id, token.approved_account_ids.keys());
+            // every approval is revoked, accounts are notified while the prepaid gas lasts
+            // gas attached to a notification counts as used gas, so each one gets its full budget
+            for account_id in token.approved_account_ids.keys() {
+                if env::prepaid_gas() - env::used_gas() < GAS_FOR_NFT_ON_REVOKE + GAS_FOR_REVOKE_ALL {
+                    break;
+                }
+                notify_revoked_account_id(&token_id, account_id);
+            }
             token.approved_account_ids.clear();
             self.tokens_by_id.insert(&token_id, &token);
         }
@@ -360,8 +388,15 @@ impl NonFungibleTokenCore for Contract {
 
     fn nft_token(&self, token_id: TokenId) -> Option<JsonToken> {
         if let Some(token) = self.tokens_by_id.get(&token_id) {
-            let metadata = self.token_metadata_by_id.get(&token_id).unwrap();
+            let metadata = self.internal_token_metadata(&token_id).unwrap();
+            let user = self.internal_active_user(&token_id);
+            let frozen = self.frozen_tokens.get(&token_id);
             Some(JsonToken {
+                frozen: frozen.is_some(),
+                frozen_reason: frozen.map(|frozen| frozen.reason),
+                user_id: user.as_ref().map(|user| user.user_id.clone()),
+                user_expires: user.map(|user| user.expires),
+                non_transferable: self.internal_is_non_transferable(&token.token_type),
                 token_id,
                 owner_id: token.owner_id,
                 metadata,
@@ -391,6 +426,7 @@ impl NonFungibleTokenResolver for Contract {
             if let Ok(return_token) = near_sdk::serde_json::from_slice::<bool>(&value) {
                 if !return_token {
                     // Token was successfully received.
+                    self.internal_remove_approval_conditions(&token_id, &owner_id, approved_account_ids.keys());
                     refund_approved_account_ids(owner_id, &approved_account_ids);
                     return true;
                 }
@@ -400,24 +436,37 @@ impl NonFungibleTokenResolver for Contract {
         let mut token = if let Some(token) = self.tokens_by_id.get(&token_id) {
             if token.owner_id != receiver_id {
                 // The token is not owner by the receiver anymore. Can't return it.
+                self.internal_remove_approval_conditions(&token_id, &owner_id, approved_account_ids.keys());
                 refund_approved_account_ids(owner_id, &approved_account_ids);
                 return true;
             }
             token
         } else {
             // The token was burned and doesn't exist anymore.
+            self.internal_remove_approval_conditions(&token_id, &owner_id, approved_account_ids.keys());
             refund_approved_account_ids(owner_id, &approved_account_ids);
             return true;
         };
 
-        log!("Return {} from @{} to @{}", token_id, receiver_id, owner_id);
 
-        self.internal_remove_token_from_owner(&receiver_id, &token_id);
-        self.internal_add_token_to_owner(&owner_id, &token_id);
+        self.internal_remove_token_from_owner(&receiver_id, &token_id, token.token_type.as_ref());
+        self.internal_add_token_to_owner(&owner_id, &token_id, token.token_type.as_ref());
         token.owner_id = owner_id;
-        refund_approved_account_ids(receiver_id, &token.approved_account_ids);
+        refund_approved_account_ids(receiver_id.clone(), &token.approved_account_ids);
+        self.internal_remove_approval_conditions(&token_id, &receiver_id, token.approved_account_ids.keys());
         token.approved_account_ids = approved_account_ids;
         self.tokens_by_id.insert(&token_id, &token);
+        self.internal_clear_user(&token_id);
+
+        let mut logs = vec![NftTransferLog {
+            authorized_id: None,
+            old_owner_id: receiver_id,
+            new_owner_id: token.owner_id.clone(),
+            token_ids: vec![token_id.clone()],
+            memo: None,
+        }];
+        self.internal_move_children(&token_id, &token.owner_id, None, &mut logs);
+        EventLog::new(EventLogVariant::NftTransfer(logs)).emit();
 
         false
     }
@@ -0,0 +1,104 @@
+use crate::*;
+
+/// bounds the gas of checking operators on every transfer
+pub const MAX_OPERATORS: usize = 20;
+
+/// CUSTOM - operator of all tokens of an owner, or of their tokens of one token_type
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Operator {
+    pub operator_id: AccountId,
+    pub token_type: Option<TokenType>,
+}
+
+/// CUSTOM - account level approvals, an operator can transfer every current and future token of the owner
+/// operators don't have approval ids and aren't cleared by transfers, only by nft_revoke_operator
+#[near_bindgen]
+impl Contract {
+    /// token_type limits the operator to tokens of that type, replaces a previous approval of operator_id
+    /// attach a deposit to cover storage
+    #[payable]
+    pub fn nft_approve_all(&mut self, operator_id: ValidAccountId, token_type: Option<TokenType>) {
+        assert_at_least_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let operator_id: AccountId = operator_id.into();
+        assert_ne!(owner_id, operator_id, "Cannot approve yourself");
+        if let Some(token_type) = token_type.as_ref() {
+            self.internal_token_type(token_type);
+        }
+        let initial_storage_usage = env::storage_usage();
+
+        let mut operators = self.operators_by_owner.get(&owner_id).unwrap_or_default();
+        operators.insert(operator_id, token_type);
+        assert!(operators.len() <= MAX_OPERATORS, "Cannot have more than {} operators", MAX_OPERATORS);
+        self.operators_by_owner.insert(&owner_id, &operators);
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_charge_storage(storage_used);
+    }
+
+    #[payable]
+    pub fn nft_revoke_operator(&mut self, operator_id: ValidAccountId) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let mut operators = self.operators_by_owner.get(&owner_id).unwrap_or_default();
+        if operators.remove(operator_id.as_ref()).is_none() {
+            return;
+        }
+        let initial_storage_usage = env::storage_usage();
+        if operators.is_empty() {
+            self.operators_by_owner.remove(&owner_id);
+        } else {
+            self.operators_by_owner.insert(&owner_id, &operators);
+        }
+        let storage_released = initial_storage_usage.saturating_sub(env::storage_usage());
+        Promise::new(owner_id).transfer(Balance::from(storage_released) * env::storage_byte_cost());
+    }
+
+    /// views
+
+    pub fn nft_operators(&self, owner_id: AccountId) -> Vec<Operator> {
+        self.operators_by_owner.get(&owner_id).unwrap_or_default().into_iter()
+            .map(|(operator_id, token_type)| Operator { operator_id, token_type })
+            .collect()
+    }
+
+    /// with a token_id, whether operator_id can transfer that token of owner_id
+    pub fn nft_is_operator(&self, owner_id: AccountId, operator_id: AccountId, token_id: Option<TokenId>) -> bool {
+        match token_id {
+            Some(token_id) => {
+                let token = self.tokens_by_id.get(&token_id).expect("No token");
+                token.owner_id == owner_id && self.internal_is_operator(&owner_id, &operator_id, &token.token_type)
+            }
+            None => self.operators_by_owner.get(&owner_id)
+                .map(|operators| operators.contains_key(&operator_id))
+                .unwrap_or(false),
+        }
+    }
+
+    /// storage cost in yoctoNEAR nft_approve_all charges owner_id for approving operator_id
+    pub fn nft_approve_all_storage_cost(
+        &self,
+        owner_id: AccountId,
+        operator_id: ValidAccountId,
+        token_type: Option<TokenType>,
+    ) -> U128 {
+        let operators = self.operators_by_owner.get(&owner_id);
+        let initial_bytes = operators.as_ref()
+            .map(|operators| storage_entry_bytes(StorageKey::OperatorsByOwner, &owner_id, operators))
+            .unwrap_or(0);
+        let mut operators = operators.unwrap_or_default();
+        operators.insert(operator_id.into(), token_type);
+        let bytes = storage_entry_bytes(StorageKey::OperatorsByOwner, &owner_id, &operators);
+        U128(Balance::from(bytes.saturating_sub(initial_bytes)) * env::storage_byte_cost())
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_is_operator(&self, owner_id: &AccountId, operator_id: &AccountId, token_type: &Option<TokenType>) -> bool {
+        self.operators_by_owner.get(owner_id)
+            .and_then(|operators| operators.get(operator_id).cloned())
+            .map(|scope| scope.is_none() || &scope == token_type)
+            .unwrap_or(false)
+    }
+}
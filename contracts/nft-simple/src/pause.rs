@@ -0,0 +1,48 @@
+use crate::*;
+
+/// CUSTOM - functionality that can be paused in an emergency
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PauseFlag {
+    /// every mint, including public mints and editions
+    Minting,
+    /// transfers by holders and approved accounts, including nesting
+    Transfers,
+}
+
+/// CUSTOM - pausers stop minting or transfers until unpaused
+/// callbacks of calls already in flight, like nft_resolve_transfer, aren't paused so they can settle
+#[near_bindgen]
+impl Contract {
+    /// only pauser
+
+    pub fn pause(&mut self, flags: Vec<PauseFlag>) {
+        self.assert_role(Role::Pauser);
+        for flag in flags {
+            if !self.paused.contains(&flag) {
+                self.paused.push(flag);
+            }
+        }
+    }
+
+    pub fn unpause(&mut self, flags: Vec<PauseFlag>) {
+        self.assert_role(Role::Pauser);
+        self.paused.retain(|flag| !flags.contains(flag));
+    }
+
+    /// views
+
+    pub fn get_paused(&self) -> Vec<PauseFlag> {
+        self.paused.clone()
+    }
+
+    pub fn is_paused(&self, flag: PauseFlag) -> bool {
+        self.paused.contains(&flag)
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_not_paused(&self, flag: PauseFlag) {
+        assert!(!self.paused.contains(&flag), "{:?} is paused", flag);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+const GAS_FOR_FT_TRANSFER: Gas = 5_000_000_000_000;
+const GAS_FOR_RESOLVE_WITHDRAW: Gas = 5_000_000_000_000;
+const NO_DEPOSIT: Balance = 0;
+/// ft_token_id used for prices in NEAR
+pub const NEAR_TOKEN_ID: &str = "near";
+static DELIMETER: &str = "||";
+
+/// CUSTOM - price to mint a token type publicly
+/// ft_token_id is "near" or a whitelisted NEP-141 contract paid with ft_transfer_call
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PublicMint {
+    pub ft_token_id: AccountId,
+    pub price: U128,
+    pub limit_per_account: Option<u64>,
+}
+
+/// msg of ft_transfer_call to mint with FTs
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PublicMintArgs {
+    pub token_type: TokenType,
+    pub receiver_id: Option<ValidAccountId>,
+}
+
+#[ext_contract(ext_fungible_token)]
+trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_proceeds_resolver)]
+trait ProceedsResolver {
+    fn resolve_withdraw_proceeds(&mut self, account_id: AccountId, ft_token_id: AccountId, amount: U128);
+}
+
+trait FungibleTokenReceiver {
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128>;
+}
+
+#[near_bindgen]
+impl Contract {
+    /// anyone can mint a token type with a NEAR price, attach price + storage
+    /// metadata comes from the token type, see token_type.rs
+    #[payable]
+    pub fn nft_public_mint(
+        &mut self,
+        token_type: TokenType,
+        receiver_id: Option<ValidAccountId>,
+    ) -> TokenId {
+        let public_mint = self.internal_token_type(&token_type).public_mint.expect("Token type is not for public mint");
+        assert_eq!(public_mint.ft_token_id, NEAR_TOKEN_ID, "Token type must be minted with ft_transfer_call");
+
+        let initial_storage_usage = env::storage_usage();
+        let buyer_id = env::predecessor_account_id();
+        let token_id = self.internal_public_mint(&public_mint, token_type, receiver_id, &buyer_id);
+        let required_storage_in_bytes = self.internal_record_mint_storage(&token_id, buyer_id, initial_storage_usage);
+
+        self.internal_charge_storage_with_cost(required_storage_in_bytes, public_mint.price.0);
+
+        token_id
+    }
+
+    /// withdraw all proceeds of the predecessor in "near" or a NEP-141 token
+    #[payable]
+    pub fn withdraw_proceeds(&mut self, ft_token_id: AccountId) -> U128 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let key = proceeds_key(&account_id, &ft_token_id);
+        let amount = self.proceeds.remove(&key).unwrap_or(0);
+        assert!(amount > 0, "No proceeds to withdraw");
+
+        if ft_token_id == NEAR_TOKEN_ID {
+            Promise::new(account_id).transfer(amount);
+        } else {
+            ext_fungible_token::ft_transfer(
+                account_id.clone(),
+                U128(amount),
+                None,
+                &ft_token_id,
+                1,
+                GAS_FOR_FT_TRANSFER,
+            )
+            .then(ext_proceeds_resolver::resolve_withdraw_proceeds(
+                account_id,
+                ft_token_id,
+                U128(amount),
+                &env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_RESOLVE_WITHDRAW,
+            ));
+        }
+        U128(amount)
+    }
+
+    /// self callback, credits the proceeds back if the FT transfer failed
+    #[private]
+    pub fn resolve_withdraw_proceeds(&mut self, account_id: AccountId, ft_token_id: AccountId, amount: U128) {
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+        self.internal_add_proceeds(&account_id, &ft_token_id, amount.0);
+    }
+
+    /// only type manager
+
+    pub fn set_public_mint(&mut self, token_type: TokenType, public_mint: Option<PublicMint>) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        if let Some(public_mint) = public_mint.as_ref() {
+            assert!(
+                public_mint.ft_token_id == NEAR_TOKEN_ID || self.mint_ft_token_ids.contains(&public_mint.ft_token_id),
+                "Token {} not supported for minting", public_mint.ft_token_id
+            );
+        }
+        config.public_mint = public_mint;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    /// only admin
+
+    pub fn add_mint_ft_token_ids(&mut self, ft_token_ids: Vec<ValidAccountId>) -> Vec<bool> {
+        self.assert_role(Role::Admin);
+        let mut added = vec![];
+        for ft_token_id in ft_token_ids {
+            added.push(self.mint_ft_token_ids.insert(ft_token_id.as_ref()));
+        }
+        added
+    }
+
+    /// shares of public mint proceeds in basis points, the contract owner receives the remainder
+    pub fn set_beneficiaries(&mut self, beneficiaries: HashMap<AccountId, u32>) {
+        self.assert_role(Role::Admin);
+        assert!(beneficiaries.values().sum::<u32>() <= 10_000, "Beneficiary shares cannot be more than 100%");
+        self.beneficiaries = beneficiaries;
+    }
+
+    /// views
+
+    pub fn get_public_mint(&self, token_type: TokenType) -> Option<PublicMint> {
+        self.internal_token_type(&token_type).public_mint
+    }
+
+    pub fn get_mint_ft_token_ids(&self) -> Vec<AccountId> {
+        self.mint_ft_token_ids.to_vec()
+    }
+
+    pub fn get_beneficiaries(&self) -> HashMap<AccountId, u32> {
+        self.beneficiaries.clone()
+    }
+
+    pub fn get_proceeds(&self, account_id: AccountId, ft_token_id: AccountId) -> U128 {
+        U128(self.proceeds.get(&proceeds_key(&account_id, &ft_token_id)).unwrap_or(0))
+    }
+
+    pub fn get_public_mints_for_account(&self, token_type: TokenType, account_id: AccountId) -> u64 {
+        self.public_mints_per_account.get(&format!("{}{}{}", token_type, DELIMETER, account_id)).unwrap_or(0)
+    }
+}
+
+/// callbacks from FT contracts, mints a token type priced in that FT
+/// the buyer pays the storage from their storage_deposit balance, the FT is refunded if it's too low
+#[near_bindgen]
+impl FungibleTokenReceiver for Contract {
+    fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        let PublicMintArgs {
+            token_type,
+            receiver_id,
+        } = near_sdk::serde_json::from_str(&msg).expect("Invalid PublicMintArgs");
+
+        let ft_token_id = env::predecessor_account_id();
+        let public_mint = self.internal_token_type(&token_type).public_mint.expect("Token type is not for public mint");
+        assert_eq!(public_mint.ft_token_id, ft_token_id, "Token type is not for sale in that token");
+        assert!(amount.0 >= public_mint.price.0, "Amount must be at least the mint price");
+
+        let initial_storage_usage = env::storage_usage();
+        let token_id = self.internal_public_mint(&public_mint, token_type, receiver_id, &sender_id);
+        let required_storage_in_bytes = self.internal_record_mint_storage(&token_id, sender_id.clone(), initial_storage_usage);
+        // the FT contract refunds the whole amount when this panics
+        assert!(
+            self.internal_try_charge_storage_balance(&sender_id, required_storage_in_bytes),
+            "Must deposit storage with storage_deposit to mint with {}",
+            ft_token_id
+        );
+
+        // refund the rest in ft_resolve_transfer
+        PromiseOrValue::Value(U128(amount.0 - public_mint.price.0))
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_public_mint(
+        &mut self,
+        public_mint: &PublicMint,
+        token_type: TokenType,
+        receiver_id: Option<ValidAccountId>,
+        buyer_id: &AccountId,
+    ) -> TokenId {
+        let mints_key = format!("{}{}{}", token_type, DELIMETER, buyer_id);
+        let mints = self.public_mints_per_account.get(&mints_key).unwrap_or(0) + 1;
+        if let Some(limit_per_account) = public_mint.limit_per_account {
+            assert!(mints <= limit_per_account, "Mint limit per account reached");
+        }
+        self.public_mints_per_account.insert(&mints_key, &mints);
+
+        // token_id contains the token_type, markets require it
+        let mut edition = self.internal_supply_for_type(&token_type) + 1;
+        let mut token_id = format!("{}:{}", token_type, edition);
+        while self.tokens_by_id.get(&token_id).is_some() {
+            edition += 1;
+            token_id = format!("{}:{}", token_type, edition);
+        }
+
+        let metadata = self.internal_token_type(&token_type).metadata.to_token_metadata(&token_id);
+        let owner_id: AccountId = receiver_id.map(|a| a.into()).unwrap_or_else(|| buyer_id.clone());
+        let token_id = self.internal_mint(Some(token_id), metadata, None, &owner_id, Some(token_type));
+
+        self.internal_split_proceeds(&public_mint.ft_token_id, public_mint.price.0);
+
+        EventLog::new(EventLogVariant::NftMint(vec![NftMintLog {
+            owner_id,
+            token_ids: vec![token_id.clone()],
+            memo: None,
+        }])).emit();
+
+        token_id
+    }
+
+    /// beneficiaries get their share, the contract owner gets the remainder
+    pub(crate) fn internal_split_proceeds(&mut self, ft_token_id: &AccountId, amount: Balance) {
+        let mut remainder = amount;
+        for (account_id, share) in self.beneficiaries.clone() {
+            let proceeds = amount * share as u128 / 10_000;
+            remainder -= proceeds;
+            self.internal_add_proceeds(&account_id, ft_token_id, proceeds);
+        }
+        let owner_id = self.owner_id.clone();
+        self.internal_add_proceeds(&owner_id, ft_token_id, remainder);
+    }
+
+    pub(crate) fn internal_add_proceeds(&mut self, account_id: &AccountId, ft_token_id: &AccountId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        let key = proceeds_key(account_id, ft_token_id);
+        let balance = self.proceeds.get(&key).unwrap_or(0);
+        self.proceeds.insert(&key, &(balance + amount));
+    }
+}
+
+fn proceeds_key(account_id: &AccountId, ft_token_id: &AccountId) -> String {
+    format!("{}{}{}", account_id, DELIMETER, ft_token_id)
+}
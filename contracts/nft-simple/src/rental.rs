@@ -0,0 +1,121 @@
+use crate::*;
+
+/// CUSTOM - account that may use a token until expires (ms), without owning it
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenUser {
+    pub user_id: AccountId,
+    pub expires: U64,
+    /// paid for the storage of this record and gets it back when the record is cleared
+    pub payer_id: AccountId,
+}
+
+/// CUSTOM - rentals, the owner or an approved account lends a token to a user until a timestamp
+/// the user is cleared when the token is transferred
+#[near_bindgen]
+impl Contract {
+    /// owner or approved account sets the user, None clears it, attach a deposit to cover storage
+    #[payable]
+    pub fn nft_set_user(&mut self, token_id: TokenId, user_id: Option<ValidAccountId>, expires: U64) {
+        assert_at_least_one_yocto();
+        self.internal_set_user(&token_id, user_id.map(|a| a.into()), expires, None, env::predecessor_account_id());
+    }
+
+    /// approved account (a market) sets the user and gets the payout of the rent, like nft_transfer_payout
+    /// attach a deposit to cover storage, the deposit is the user's, what's left is refunded to them
+    #[payable]
+    pub fn nft_set_user_payout(
+        &mut self,
+        token_id: TokenId,
+        user_id: ValidAccountId,
+        expires: U64,
+        approval_id: u64,
+        balance: U128,
+        max_len_payout: u32,
+    ) -> Payout {
+        assert_at_least_one_yocto();
+        let user_id: AccountId = user_id.into();
+        let token = self.internal_set_user(&token_id, Some(user_id.clone()), expires, Some(approval_id), user_id);
+        self.internal_payout(&token, balance.0, max_len_payout)
+    }
+
+    /// views
+
+    /// user at the current block time, None if there is none or it expired
+    pub fn nft_user_of(&self, token_id: TokenId) -> Option<AccountId> {
+        self.internal_active_user(&token_id).map(|user| user.user_id)
+    }
+
+    pub fn nft_user_expires(&self, token_id: TokenId) -> Option<U64> {
+        self.internal_active_user(&token_id).map(|user| user.expires)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_active_user(&self, token_id: &TokenId) -> Option<TokenUser> {
+        self.token_users.get(token_id)
+            .filter(|user| user.expires.0 > env::block_timestamp() / 1_000_000)
+    }
+
+    /// an approved account can't replace a user that hasn't expired, the owner can
+    /// the replaced record is refunded to its payer, payer_id pays for the new one and gets the rest of the deposit
+    fn internal_set_user(
+        &mut self,
+        token_id: &TokenId,
+        user_id: Option<AccountId>,
+        expires: U64,
+        approval_id: Option<u64>,
+        payer_id: AccountId,
+    ) -> Token {
+        let token = self.tokens_by_id.get(token_id).expect("No token");
+        let predecessor_account_id = env::predecessor_account_id();
+        if predecessor_account_id != token.owner_id {
+            let actual_approval_id = token.approved_account_ids.get(&predecessor_account_id).expect("Unauthorized");
+            self.assert_approval_not_expired(token_id, &token, &predecessor_account_id);
+            if let Some(approval_id) = approval_id {
+                assert_eq!(
+                    actual_approval_id, &approval_id,
+                    "The actual approval_id {} is different from the given approval_id {}",
+                    actual_approval_id, approval_id,
+                );
+            }
+            if let Some(user) = self.internal_active_user(token_id) {
+                assert!(Some(&user.user_id) == user_id.as_ref(), "Token is rented until {}", user.expires.0);
+            }
+        }
+        self.assert_token_valid(token_id, &token);
+        self.assert_not_nested(token_id);
+        self.assert_not_frozen(token_id);
+        assert!(!self.internal_is_non_transferable(&token.token_type), "Tokens of this type cannot be rented");
+
+        self.internal_clear_user(token_id);
+        let initial_storage_usage = env::storage_usage();
+        if let Some(user_id) = user_id.as_ref() {
+            assert!(expires.0 > env::block_timestamp() / 1_000_000, "expires must be in the future");
+            self.token_users.insert(token_id, &TokenUser { user_id: user_id.clone(), expires, payer_id: payer_id.clone() });
+        }
+        let storage_used = env::storage_usage() - initial_storage_usage;
+        self.internal_charge_storage_refund_to(storage_used, 0, payer_id);
+
+        EventLog::new(EventLogVariant::NftUpdateUser(vec![NftUpdateUserLog {
+            authorized_id: if predecessor_account_id != token.owner_id { Some(predecessor_account_id) } else { None },
+            owner_id: token.owner_id.clone(),
+            user_id,
+            expires,
+            token_id: token_id.clone(),
+        }])).emit();
+        token
+    }
+
+    /// removes the user, refunding the freed storage to whoever paid for it
+    pub(crate) fn internal_clear_user(&mut self, token_id: &TokenId) {
+        let initial_storage_usage = env::storage_usage();
+        let user = if let Some(user) = self.token_users.remove(token_id) {
+            user
+        } else {
+            return;
+        };
+        let storage_released = initial_storage_usage - env::storage_usage();
+        Promise::new(user.payer_id).transfer(Balance::from(storage_released) * env::storage_byte_cost());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::*;
+
+/// CUSTOM - roles that can be granted by an admin, the contract owner implicitly has every role
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// grant and revoke roles, contract level settings
+    Admin,
+    /// mint tokens, optionally restricted to some token types
+    Minter,
+    /// add, lock and unlock token types and their settings
+    TypeManager,
+    /// pause contract functionality
+    Pauser,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// only admin
+
+    pub fn grant_role(&mut self, account_id: ValidAccountId, role: Role) -> bool {
+        self.assert_role(Role::Admin);
+        let account_id: AccountId = account_id.into();
+        let mut roles = self.roles_by_account.get(&account_id).unwrap_or_default();
+        if roles.contains(&role) {
+            return false;
+        }
+        roles.push(role);
+        self.roles_by_account.insert(&account_id, &roles);
+        true
+    }
+
+    pub fn revoke_role(&mut self, account_id: ValidAccountId, role: Role) -> bool {
+        self.assert_role(Role::Admin);
+        let account_id: AccountId = account_id.into();
+        let mut roles = self.roles_by_account.get(&account_id).unwrap_or_default();
+        let len = roles.len();
+        roles.retain(|r| r != &role);
+        if roles.len() == len {
+            return false;
+        }
+        if roles.is_empty() {
+            self.roles_by_account.remove(&account_id);
+        } else {
+            self.roles_by_account.insert(&account_id, &roles);
+        }
+        if role == Role::Minter {
+            self.minter_token_types.remove(&account_id);
+        }
+        true
+    }
+
+    /// restrict a minter to some token types, None lets them mint any type
+    pub fn set_minter_token_types(&mut self, account_id: ValidAccountId, token_types: Option<Vec<TokenType>>) {
+        self.assert_role(Role::Admin);
+        if let Some(token_types) = token_types {
+            self.minter_token_types.insert(account_id.as_ref(), &token_types);
+        } else {
+            self.minter_token_types.remove(account_id.as_ref());
+        }
+    }
+
+    /// views
+
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        self.internal_has_role(&account_id, role)
+    }
+
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        self.roles_by_account.get(&account_id).unwrap_or_default()
+    }
+
+    pub fn get_role_holders(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<(AccountId, Vec<Role>)> {
+        let start = u64::from(from_index.unwrap_or(U64(0)));
+        self.roles_by_account.iter()
+            .skip(start as usize)
+            .take(page_limit(limit) as usize)
+            .collect()
+    }
+
+    pub fn get_minter_token_types(&self, account_id: AccountId) -> Option<Vec<TokenType>> {
+        self.minter_token_types.get(&account_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_has_role(&self, account_id: &AccountId, role: Role) -> bool {
+        account_id == &self.owner_id
+            || self.roles_by_account.get(account_id).map(|roles| roles.contains(&role)).unwrap_or(false)
+    }
+
+    pub(crate) fn assert_role(&self, role: Role) {
+        assert!(
+            self.internal_has_role(&env::predecessor_account_id(), role),
+            "Requires role {:?}",
+            role
+        );
+    }
+
+    /// minters can be limited to some token types, untyped tokens need an unrestricted minter
+    pub(crate) fn assert_minter(&self, token_type: &Option<TokenType>) {
+        self.assert_role(Role::Minter);
+        if let Some(token_types) = self.minter_token_types.get(&env::predecessor_account_id()) {
+            let allowed = token_type.as_ref().map(|token_type| token_types.contains(token_type)).unwrap_or(false);
+            assert!(allowed, "Minter can't mint this token type");
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::*;
+
+/// admin reassignments can be executed this long after they are proposed, in milliseconds
+pub const ROYALTY_REASSIGNMENT_DELAY: u64 = 7 * 24 * 60 * 60 * 1000;
+
+/// CUSTOM - one change of a token's royalties, kept in get_royalty_history
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoyaltyChange {
+    pub from_id: AccountId,
+    pub to_id: AccountId,
+    /// basis points moved
+    pub amount: u32,
+    /// royalty holder or the admin that executed a reassignment
+    pub authorized_id: AccountId,
+    pub changed_at: U64,
+}
+
+/// CUSTOM - admin reassignment of a royalty, e.g. a lost recipient account
+/// the royalty holder or an admin can cancel it before executable_at
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoyaltyReassignment {
+    pub to_id: AccountId,
+    pub proposed_by: AccountId,
+    pub executable_at: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// royalty holder moves amount basis points (default all) of their royalty to receiver_id
+    /// attach a deposit to cover storage if receiver_id is a new recipient
+    #[payable]
+    pub fn nft_transfer_royalty(&mut self, token_id: TokenId, receiver_id: ValidAccountId, amount: Option<u32>) {
+        assert_at_least_one_yocto();
+        let initial_storage_usage = env::storage_usage();
+        let predecessor_account_id = env::predecessor_account_id();
+        self.internal_move_royalty(&token_id, &predecessor_account_id, receiver_id.as_ref(), amount, &predecessor_account_id);
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_charge_storage(storage_used);
+    }
+
+    /// only admin - starts the timelock of moving the whole royalty of from_id to to_id
+    pub fn propose_royalty_reassignment(&mut self, token_id: TokenId, from_id: AccountId, to_id: ValidAccountId) {
+        self.assert_role(Role::Admin);
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        assert!(token.royalty.contains_key(&from_id), "No royalty for account");
+
+        let mut pending = self.pending_royalty_reassignments.get(&token_id).unwrap_or_default();
+        pending.insert(from_id, RoyaltyReassignment {
+            to_id: to_id.into(),
+            proposed_by: env::predecessor_account_id(),
+            executable_at: U64(env::block_timestamp() / 1_000_000 + ROYALTY_REASSIGNMENT_DELAY),
+        });
+        self.pending_royalty_reassignments.insert(&token_id, &pending);
+    }
+
+    /// only admin - after the timelock has passed
+    pub fn execute_royalty_reassignment(&mut self, token_id: TokenId, from_id: AccountId) {
+        self.assert_role(Role::Admin);
+        let reassignment = self.internal_remove_royalty_reassignment(&token_id, &from_id);
+        assert!(
+            env::block_timestamp() / 1_000_000 >= reassignment.executable_at.0,
+            "Reassignment is timelocked until {}", reassignment.executable_at.0
+        );
+        self.internal_move_royalty(&token_id, &from_id, &reassignment.to_id, None, &env::predecessor_account_id());
+    }
+
+    /// royalty holder or admin
+    pub fn cancel_royalty_reassignment(&mut self, token_id: TokenId, from_id: AccountId) {
+        let predecessor_account_id = env::predecessor_account_id();
+        assert!(
+            predecessor_account_id == from_id || self.internal_has_role(&predecessor_account_id, Role::Admin),
+            "Only the royalty holder or an admin can cancel"
+        );
+        self.internal_remove_royalty_reassignment(&token_id, &from_id);
+    }
+
+    /// views
+
+    pub fn get_royalty_history(&self, token_id: TokenId) -> Vec<RoyaltyChange> {
+        self.royalty_history.get(&token_id).unwrap_or_default()
+    }
+
+    pub fn get_pending_royalty_reassignments(&self, token_id: TokenId) -> HashMap<AccountId, RoyaltyReassignment> {
+        self.pending_royalty_reassignments.get(&token_id).unwrap_or_default()
+    }
+}
+
+impl Contract {
+    /// moves royalty basis points between accounts, the total and so the royalty caps don't change
+    pub(crate) fn internal_move_royalty(
+        &mut self,
+        token_id: &TokenId,
+        from_id: &AccountId,
+        to_id: &AccountId,
+        amount: Option<u32>,
+        authorized_id: &AccountId,
+    ) {
+        assert_ne!(from_id, to_id, "Cannot transfer royalty to the same account");
+        let mut token = self.tokens_by_id.get(token_id).expect("No token");
+        let held = *token.royalty.get(from_id).expect("No royalty for account");
+        let amount = amount.unwrap_or(held);
+        assert!(amount > 0 && amount <= held, "Amount must be between 1 and {}", held);
+
+        if amount == held {
+            token.royalty.remove(from_id);
+        } else {
+            token.royalty.insert(from_id.clone(), held - amount);
+        }
+        *token.royalty.entry(to_id.clone()).or_insert(0) += amount;
+        assert!(token.royalty.len() < 7, "Cannot add more than 6 perpetual royalty amounts");
+        self.tokens_by_id.insert(token_id, &token);
+
+        let mut history = self.royalty_history.get(token_id).unwrap_or_default();
+        history.push(RoyaltyChange {
+            from_id: from_id.clone(),
+            to_id: to_id.clone(),
+            amount,
+            authorized_id: authorized_id.clone(),
+            changed_at: U64(env::block_timestamp() / 1_000_000),
+        });
+        self.royalty_history.insert(token_id, &history);
+    }
+
+    fn internal_remove_royalty_reassignment(&mut self, token_id: &TokenId, from_id: &AccountId) -> RoyaltyReassignment {
+        let mut pending = self.pending_royalty_reassignments.get(token_id).unwrap_or_default();
+        let reassignment = pending.remove(from_id).expect("No pending reassignment");
+        if pending.is_empty() {
+            self.pending_royalty_reassignments.remove(token_id);
+        } else {
+            self.pending_royalty_reassignments.insert(token_id, &pending);
+        }
+        reassignment
+    }
+}
@@ -0,0 +1,222 @@
+use crate::*;
+
+pub type SeriesId = String;
+/// series_id and edition number are joined with this to make the token_id of an edition
+pub const EDITION_DELIMETER: &str = ":";
+
+/// CUSTOM - a design minted as numbered editions
+/// editions only store the metadata fields they override, see internal_token_metadata
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Series {
+    pub creator_id: AccountId,
+    pub metadata: TokenMetadata,
+    /// max editions, None is unlimited
+    pub copies: Option<u64>,
+    /// perpetual royalties of every edition
+    pub royalty: HashMap<AccountId, u32>,
+    /// anyone can buy an edition for this price in NEAR, None is creator mints only
+    pub price: Option<U128>,
+    /// editions minted so far, burned editions are not reissued
+    pub minted: u64,
+    /// editions burned so far, minted - burned are live
+    pub burned: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonSeries {
+    pub series_id: SeriesId,
+    #[serde(flatten)]
+    pub series: Series,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// minters create a series, attach a deposit to cover storage
+    #[payable]
+    pub fn nft_create_series(
+        &mut self,
+        series_id: SeriesId,
+        metadata: TokenMetadata,
+        copies: Option<u64>,
+        royalty: Option<HashMap<AccountId, u32>>,
+        price: Option<U128>,
+    ) {
+        self.assert_role(Role::Minter);
+        assert!(!series_id.is_empty() && !series_id.contains(EDITION_DELIMETER), "Invalid series_id");
+        // editions and public mints of a token type share the "prefix:number" token ids
+        assert!(self.token_types.get(&series_id).is_none(), "series_id is a token type");
+        let royalty = royalty.unwrap_or_default();
+        assert!(royalty.len() < 7, "Cannot add more than 6 perpetual royalty amounts");
+        assert!(royalty.values().sum::<u32>() <= MINTER_ROYALTY_CAP, "Perpetual royalties cannot be more than 20%");
+
+        let initial_storage_usage = env::storage_usage();
+        let series = Series {
+            creator_id: env::predecessor_account_id(),
+            metadata: TokenMetadata { copies, ..metadata },
+            copies,
+            royalty,
+            price,
+            minted: 0,
+            burned: 0,
+        };
+        assert!(self.series.insert(&series_id, &series).is_none(), "Series exists");
+
+        self.internal_charge_storage(env::storage_usage() - initial_storage_usage);
+    }
+
+    /// only the creator - mint the next edition, metadata only needs the fields that differ from the series
+    #[payable]
+    pub fn nft_mint_edition(
+        &mut self,
+        series_id: SeriesId,
+        metadata: Option<TokenMetadata>,
+        receiver_id: Option<ValidAccountId>,
+    ) -> TokenId {
+        let series = self.internal_series(&series_id);
+        assert_eq!(env::predecessor_account_id(), series.creator_id, "Only the creator can mint editions");
+
+        let initial_storage_usage = env::storage_usage();
+        let owner_id: AccountId = receiver_id.map(|a| a.into()).unwrap_or_else(env::predecessor_account_id);
+        let token_id = self.internal_mint_edition(series_id, series, metadata, &owner_id);
+        let required_storage_in_bytes =
+            self.internal_record_mint_storage(&token_id, env::predecessor_account_id(), initial_storage_usage);
+        self.internal_charge_storage(required_storage_in_bytes);
+
+        token_id
+    }
+
+    /// anyone can buy the next edition of a series with a price, attach price + storage
+    /// proceeds go to the creator, see withdraw_proceeds
+    #[payable]
+    pub fn nft_buy_edition(&mut self, series_id: SeriesId, receiver_id: Option<ValidAccountId>) -> TokenId {
+        let series = self.internal_series(&series_id);
+        let price = series.price.expect("Series is not for sale").0;
+        let creator_id = series.creator_id.clone();
+
+        let initial_storage_usage = env::storage_usage();
+        let owner_id: AccountId = receiver_id.map(|a| a.into()).unwrap_or_else(env::predecessor_account_id);
+        let token_id = self.internal_mint_edition(series_id, series, None, &owner_id);
+        self.internal_add_proceeds(&creator_id, &NEAR_TOKEN_ID.to_string(), price);
+        let required_storage_in_bytes =
+            self.internal_record_mint_storage(&token_id, env::predecessor_account_id(), initial_storage_usage);
+        self.internal_charge_storage_with_cost(required_storage_in_bytes, price);
+
+        token_id
+    }
+
+    /// only the creator - None stops sales
+    pub fn set_series_price(&mut self, series_id: SeriesId, price: Option<U128>) {
+        let mut series = self.internal_series(&series_id);
+        assert_eq!(env::predecessor_account_id(), series.creator_id, "Only the creator can set the price");
+        series.price = price;
+        self.series.insert(&series_id, &series);
+    }
+
+    /// views
+
+    pub fn get_series(&self, series_id: SeriesId) -> Option<JsonSeries> {
+        self.series.get(&series_id).map(|series| JsonSeries { series_id, series })
+    }
+
+    pub fn get_series_list(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<JsonSeries> {
+        let start = u64::from(from_index.unwrap_or(U64(0)));
+        self.series.iter()
+            .skip(start as usize)
+            .take(page_limit(limit) as usize)
+            .map(|(series_id, series)| JsonSeries { series_id, series })
+            .collect()
+    }
+
+    pub fn get_token_series(&self, token_id: TokenId) -> Option<SeriesId> {
+        self.series_by_token.get(&token_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_series(&self, series_id: &SeriesId) -> Series {
+        self.series.get(series_id).expect("No series")
+    }
+
+    /// mints "series_id:edition", callers handle storage
+    pub(crate) fn internal_mint_edition(
+        &mut self,
+        series_id: SeriesId,
+        mut series: Series,
+        metadata: Option<TokenMetadata>,
+        owner_id: &AccountId,
+    ) -> TokenId {
+        if let Some(copies) = series.copies {
+            assert!(series.minted < copies, "All editions are minted");
+        }
+        series.minted += 1;
+        // explicitly minted tokens can take an edition's id, skip them like public mints do
+        let mut edition = series.minted;
+        let mut token_id = format!("{}{}{}", series_id, EDITION_DELIMETER, edition);
+        while self.tokens_by_id.get(&token_id).is_some() {
+            edition += 1;
+            token_id = format!("{}{}{}", series_id, EDITION_DELIMETER, edition);
+        }
+
+        let overrides = TokenMetadata {
+            issued_at: Some(env::block_timestamp() / 1_000_000),
+            ..metadata.unwrap_or_else(empty_token_metadata)
+        };
+        let royalty = series.royalty.clone();
+        self.series.insert(&series_id, &series);
+        self.series_by_token.insert(&token_id, &series_id);
+        let token_id = self.internal_mint(Some(token_id), overrides, Some(royalty), owner_id, None);
+        self.internal_index_attributes(&token_id);
+
+        EventLog::new(EventLogVariant::NftMint(vec![NftMintLog {
+            owner_id: owner_id.clone(),
+            token_ids: vec![token_id.clone()],
+            memo: None,
+        }])).emit();
+
+        token_id
+    }
+
+    /// metadata of a token, editions are merged with the metadata of their series
+    pub(crate) fn internal_token_metadata(&self, token_id: &TokenId) -> Option<TokenMetadata> {
+        let metadata = self.token_metadata_by_id.get(token_id)?;
+        let series = if let Some(series_id) = self.series_by_token.get(token_id) {
+            self.internal_series(&series_id)
+        } else {
+            return Some(metadata);
+        };
+        let template = series.metadata;
+        Some(TokenMetadata {
+            title: metadata.title.or(template.title),
+            description: metadata.description.or(template.description),
+            media: metadata.media.or(template.media),
+            media_hash: metadata.media_hash.or(template.media_hash),
+            copies: metadata.copies.or(template.copies),
+            issued_at: metadata.issued_at.or(template.issued_at),
+            expires_at: metadata.expires_at.or(template.expires_at),
+            starts_at: metadata.starts_at.or(template.starts_at),
+            updated_at: metadata.updated_at.or(template.updated_at),
+            extra: metadata.extra.or(template.extra),
+            reference: metadata.reference.or(template.reference),
+            reference_hash: metadata.reference_hash.or(template.reference_hash),
+        })
+    }
+}
+
+fn empty_token_metadata() -> TokenMetadata {
+    TokenMetadata {
+        title: None,
+        description: None,
+        media: None,
+        media_hash: None,
+        copies: None,
+        issued_at: None,
+        expires_at: None,
+        starts_at: None,
+        updated_at: None,
+        extra: None,
+        reference: None,
+        reference_hash: None,
+    }
+}
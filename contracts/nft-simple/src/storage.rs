@@ -0,0 +1,236 @@
+use crate::*;
+
+/// NEAR charges this many bytes for every record in state on top of key and value
+const STORAGE_BYTES_PER_RECORD: u64 = 40;
+/// storage_deposits entry of the longest account id: prefix, account id, balance and record
+const STORAGE_BALANCE_BYTES: u64 = 1 + 4 + 64 + 16 + STORAGE_BYTES_PER_RECORD;
+
+/// NEP-145 storage balance, the minimum stays locked for the storage_deposits entry
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+/// CUSTOM - NEP-145, prepaid storage for mints and approvals
+/// storage that isn't covered by the attached deposit is taken from the balance of the predecessor
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn storage_deposit(&mut self, account_id: Option<ValidAccountId>, registration_only: Option<bool>) -> StorageBalance {
+        let account_id: AccountId = account_id.map(|a| a.into()).unwrap_or_else(env::predecessor_account_id);
+        let deposit = env::attached_deposit();
+        let min = self.storage_balance_bounds().min.0;
+
+        let mut refund = 0;
+        let balance = if let Some(balance) = self.storage_deposits.get(&account_id) {
+            if registration_only.unwrap_or(false) {
+                refund = deposit;
+                balance
+            } else {
+                balance + deposit
+            }
+        } else {
+            assert!(deposit >= min, "Requires minimum deposit of {}", min);
+            if registration_only.unwrap_or(false) {
+                refund = deposit - min;
+                min
+            } else {
+                deposit
+            }
+        };
+        self.storage_deposits.insert(&account_id, &balance);
+        if refund > 0 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    /// withdraw amount (default all) of the available balance
+    #[payable]
+    pub fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let balance = self.storage_deposits.get(&account_id).expect("Account is not registered");
+        let available = self.internal_storage_available(&account_id);
+        let amount = amount.map(|a| a.0).unwrap_or(available);
+        assert!(amount <= available, "Cannot withdraw more than {}", available);
+
+        if amount > 0 {
+            self.storage_deposits.insert(&account_id, &(balance - amount));
+            Promise::new(account_id.clone()).transfer(amount);
+        }
+
+        self.internal_storage_balance_of(&account_id).unwrap()
+    }
+
+    /// views
+
+    pub fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        StorageBalanceBounds {
+            min: U128(Balance::from(STORAGE_BALANCE_BYTES) * env::storage_byte_cost()),
+            max: None,
+        }
+    }
+
+    pub fn storage_balance_of(&self, account_id: ValidAccountId) -> Option<StorageBalance> {
+        self.internal_storage_balance_of(account_id.as_ref())
+    }
+
+    /// storage cost in yoctoNEAR nft_mint charges for these arguments
+    /// payer_id is the caller of nft_mint, default receiver_id
+    pub fn nft_mint_storage_cost(
+        &self,
+        token_id: Option<TokenId>,
+        metadata: TokenMetadata,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+        receiver_id: ValidAccountId,
+        token_type: Option<TokenType>,
+        payer_id: Option<ValidAccountId>,
+    ) -> U128 {
+        let owner_id: AccountId = receiver_id.into();
+        let payer_id: AccountId = payer_id.map(|a| a.into()).unwrap_or_else(|| owner_id.clone());
+        let token_id = token_id.unwrap_or_else(|| self.internal_next_token_id().to_string());
+        let token = Token {
+            owner_id: owner_id.clone(),
+            approved_account_ids: Default::default(),
+            next_approval_id: 0,
+            royalty: self.internal_mint_royalty(&token_type, perpetual_royalties),
+            token_type: token_type.clone(),
+        };
+        let token_id_bytes = token_id.try_to_vec().unwrap().len() as u64;
+
+        // tokens_by_id
+        let mut bytes = storage_key_bytes(StorageKey::TokensById) + token_id_bytes
+            + token.try_to_vec().unwrap().len() as u64 + STORAGE_BYTES_PER_RECORD;
+        // token_metadata_by_id: key index, key and value
+        let metadata_prefix_bytes = storage_key_bytes(StorageKey::TokenMetadataById) + 1;
+        bytes += metadata_prefix_bytes + token_id_bytes + 8 + STORAGE_BYTES_PER_RECORD;
+        bytes += metadata_prefix_bytes + 8 + token_id_bytes + STORAGE_BYTES_PER_RECORD;
+        bytes += metadata_prefix_bytes + 8 + metadata.try_to_vec().unwrap().len() as u64 + STORAGE_BYTES_PER_RECORD;
+        // mint_storage_by_token
+        bytes += storage_entry_bytes(StorageKey::MintStorageByToken, &token_id, &MintStorage { payer_id, storage_used: 0 });
+        // tokens_per_owner
+        bytes += unordered_set_insert_bytes(
+            StorageKey::TokenPerOwnerInner { account_id_hash: hash_account_id(&owner_id) },
+            token_id_bytes,
+        );
+        if self.tokens_per_owner.get(&owner_id).is_none() {
+            bytes += new_set_entry_bytes(
+                StorageKey::TokensPerOwner,
+                &owner_id,
+                StorageKey::TokenPerOwnerInner { account_id_hash: hash_account_id(&owner_id) },
+            );
+        }
+        // tokens_per_type
+        if let Some(token_type) = token_type {
+            bytes += unordered_set_insert_bytes(
+                StorageKey::TokensPerTypeInner { token_type_hash: hash_account_id(&token_type) },
+                token_id_bytes,
+            );
+            if self.tokens_per_type.get(&token_type).is_none() {
+                bytes += new_set_entry_bytes(
+                    StorageKey::TokensPerType,
+                    &token_type,
+                    StorageKey::TokensPerTypeInner { token_type_hash: hash_account_id(&token_type) },
+                );
+            }
+            // tokens_per_owner_type
+            let key = owner_type_key(&owner_id, &token_type);
+            bytes += unordered_set_insert_bytes(
+                StorageKey::TokensPerOwnerTypeInner { owner_type_hash: hash_account_id(&key) },
+                token_id_bytes,
+            );
+            if self.tokens_per_owner_type.get(&key).is_none() {
+                bytes += new_set_entry_bytes(
+                    StorageKey::TokensPerOwnerType,
+                    &key,
+                    StorageKey::TokensPerOwnerTypeInner { owner_type_hash: hash_account_id(&key) },
+                );
+            }
+        }
+
+        U128(Balance::from(self.extra_storage_in_bytes_per_token + bytes) * env::storage_byte_cost())
+    }
+
+    /// storage cost in yoctoNEAR nft_approve charges for approving account_id with the given conditions
+    /// an existing approval only pays for new conditions
+    pub fn nft_approve_storage_cost(
+        &self,
+        token_id: TokenId,
+        account_id: ValidAccountId,
+        expires_at: Option<U64>,
+        min_price: Option<U128>,
+    ) -> U128 {
+        let token = self.tokens_by_id.get(&token_id).expect("No token");
+        let mut bytes = 0;
+        if !token.approved_account_ids.contains_key(account_id.as_ref()) {
+            bytes += bytes_for_approved_account_id(account_id.as_ref());
+        }
+        let key = approval_key(&token_id, account_id.as_ref());
+        if (expires_at.is_some() || min_price.is_some()) && self.approval_conditions.get(&key).is_none() {
+            let conditions = ApprovalConditions { approval_id: token.next_approval_id, expires_at, min_price };
+            bytes += storage_key_bytes(StorageKey::ApprovalConditions) + key.try_to_vec().unwrap().len() as u64
+                + conditions.try_to_vec().unwrap().len() as u64 + STORAGE_BYTES_PER_RECORD;
+        }
+        U128(Balance::from(bytes) * env::storage_byte_cost())
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
+        self.storage_deposits.get(account_id).map(|total| StorageBalance {
+            total: U128(total),
+            available: U128(self.internal_storage_available(account_id)),
+        })
+    }
+
+    pub(crate) fn internal_storage_available(&self, account_id: &AccountId) -> Balance {
+        self.storage_deposits.get(account_id)
+            .map(|balance| balance.saturating_sub(self.storage_balance_bounds().min.0))
+            .unwrap_or(0)
+    }
+
+    /// next numeric id from the next_token_id counter, skipping ids that were minted explicitly
+    /// the counter never goes down, so ids of burned tokens aren't reused
+    pub(crate) fn internal_next_token_id(&self) -> u64 {
+        let mut next_token_id = self.next_token_id;
+        while self.tokens_by_id.get(&next_token_id.to_string()).is_some() {
+            next_token_id += 1;
+        }
+        next_token_id
+    }
+}
+
+fn storage_key_bytes(storage_key: StorageKey) -> u64 {
+    storage_key.try_to_vec().unwrap().len() as u64
+}
+
+/// entry of a LookupMap: prefix, key, value and record
+pub(crate) fn storage_entry_bytes<V: BorshSerialize>(storage_key: StorageKey, key: &str, value: &V) -> u64 {
+    storage_key_bytes(storage_key) + key.to_string().try_to_vec().unwrap().len() as u64
+        + value.try_to_vec().unwrap().len() as u64 + STORAGE_BYTES_PER_RECORD
+}
+
+/// new element of an UnorderedSet: element index and element
+fn unordered_set_insert_bytes(storage_key: StorageKey, element_bytes: u64) -> u64 {
+    let prefix_bytes = storage_key_bytes(storage_key) + 1;
+    (prefix_bytes + element_bytes + 8 + STORAGE_BYTES_PER_RECORD)
+        + (prefix_bytes + 8 + element_bytes + STORAGE_BYTES_PER_RECORD)
+}
+
+/// LookupMap entry holding a new UnorderedSet
+fn new_set_entry_bytes(storage_key: StorageKey, key: &str, set_key: StorageKey) -> u64 {
+    let set: UnorderedSet<TokenId> = UnorderedSet::new(set_key.try_to_vec().unwrap());
+    storage_key_bytes(storage_key) + key.to_string().try_to_vec().unwrap().len() as u64
+        + set.try_to_vec().unwrap().len() as u64 + STORAGE_BYTES_PER_RECORD
+}
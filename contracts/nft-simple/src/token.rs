@@ -1,7 +1,13 @@
 use crate::*;
 
 pub type TokenId = String;
-pub type Payout = HashMap<AccountId, U128>;
+
+/// NEP-199 payout, amounts add up to exactly the balance that was paid
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Token {
@@ -25,4 +31,12 @@ pub struct JsonToken {
     // CUSTOM - fields
     pub royalty: HashMap<AccountId, u32>,
     pub token_type: Option<String>,
+    /// token_type is soulbound, see TransferPolicy::NonTransferable
+    pub non_transferable: bool,
+    /// rental user at the current block time, see nft_set_user
+    pub user_id: Option<AccountId>,
+    pub user_expires: Option<U64>,
+    /// see nft_freeze
+    pub frozen: bool,
+    pub frozen_reason: Option<String>,
 }
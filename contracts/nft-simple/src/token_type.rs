@@ -0,0 +1,208 @@
+use crate::*;
+
+/// CUSTOM - who may move tokens of a token_type (locking is separate and temporary)
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TransferPolicy {
+    /// holders and approved accounts can transfer
+    Anyone,
+    /// only the holder can transfer, approvals are not allowed
+    HolderOnly,
+    /// soulbound, tokens never move except by admin recovery and can always be burned by their holder
+    /// permanent once set
+    NonTransferable,
+}
+
+/// CUSTOM - shared metadata of a token_type, "{token_id}" is replaced when minting from it
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenTypeMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>, // media template e.g. "https://example.com/{token_id}.png"
+}
+
+impl TokenTypeMetadata {
+    pub fn to_token_metadata(&self, token_id: &str) -> TokenMetadata {
+        let render = |template: &Option<String>| template.as_ref().map(|t| t.replace("{token_id}", token_id));
+        TokenMetadata {
+            title: render(&self.title),
+            description: render(&self.description),
+            media: render(&self.media),
+            media_hash: None,
+            copies: None,
+            issued_at: Some(env::block_timestamp() / 1_000_000),
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+}
+
+/// CUSTOM - everything known about a token_type
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenTypeConfig {
+    pub cap: U64,
+    pub locked: bool,
+    /// locked tokens become transferable at this time, Unix epoch in milliseconds
+    pub unlock_at: Option<U64>,
+    /// perpetual royalties added to every token minted of this type
+    pub royalty: HashMap<AccountId, u32>,
+    pub metadata: TokenTypeMetadata,
+    pub public_mint: Option<PublicMint>,
+    pub transfer_policy: TransferPolicy,
+    pub burn_policy: BurnPolicy,
+    pub validity_policy: ValidityPolicy,
+    pub created_at: U64,
+}
+
+impl TokenTypeConfig {
+    pub fn new(cap: U64, locked: bool, unlock_at: Option<U64>) -> Self {
+        Self {
+            cap,
+            locked,
+            unlock_at,
+            royalty: HashMap::new(),
+            metadata: TokenTypeMetadata::default(),
+            public_mint: None,
+            transfer_policy: TransferPolicy::Anyone,
+            burn_policy: BurnPolicy::OwnerWhileLockedOrHolder,
+            validity_policy: ValidityPolicy::Ignore,
+            created_at: U64(env::block_timestamp() / 1_000_000),
+        }
+    }
+
+    /// locked until unlocked by a type manager or until unlock_at has passed
+    pub fn is_locked(&self) -> bool {
+        self.locked && self.unlock_at.map(|unlock_at| env::block_timestamp() / 1_000_000 < unlock_at.0).unwrap_or(true)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonTokenType {
+    pub token_type: TokenType,
+    pub supply: U64,
+    /// current lock state, taking unlock_at into account
+    pub is_locked: bool,
+    #[serde(flatten)]
+    pub config: TokenTypeConfig,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// only type manager
+
+    pub fn set_token_type_cap(&mut self, token_type: TokenType, cap: U64) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        assert!(cap.0 >= self.internal_supply_for_type(&token_type), "Cap cannot be less than supply");
+        config.cap = cap;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    pub fn set_token_type_royalty(&mut self, token_type: TokenType, royalty: HashMap<AccountId, u32>) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        assert!(royalty.len() < 7, "Cannot add more than 6 perpetual royalty amounts");
+        assert!(royalty.values().sum::<u32>() <= MINTER_ROYALTY_CAP, "Perpetual royalties cannot be more than 20%");
+        config.royalty = royalty;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    pub fn set_token_type_metadata(&mut self, token_type: TokenType, metadata: TokenTypeMetadata) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        config.metadata = metadata;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    pub fn set_token_type_transfer_policy(&mut self, token_type: TokenType, transfer_policy: TransferPolicy) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        assert!(
+            config.transfer_policy != TransferPolicy::NonTransferable,
+            "Non-transferable token types cannot be changed"
+        );
+        config.transfer_policy = transfer_policy;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    /// schedule when a locked token_type becomes transferable, None keeps it locked until unlocked
+    pub fn set_token_type_unlock_at(&mut self, token_type: TokenType, unlock_at: Option<U64>) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        config.unlock_at = unlock_at;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    pub fn lock_token_types(&mut self, token_types: Vec<TokenType>) {
+        self.assert_role(Role::TypeManager);
+        for token_type in &token_types {
+            let mut config = self.internal_token_type(token_type);
+            config.locked = true;
+            self.token_types.insert(token_type, &config);
+        }
+    }
+
+    /// views
+
+    pub fn get_token_type(&self, token_type: TokenType) -> Option<JsonTokenType> {
+        self.token_types.get(&token_type).map(|config| JsonTokenType {
+            supply: U64(self.internal_supply_for_type(&token_type)),
+            is_locked: config.is_locked(),
+            token_type,
+            config,
+        })
+    }
+
+    pub fn get_token_type_unlock_at(&self, token_type: TokenType) -> Option<U64> {
+        self.internal_token_type(&token_type).unlock_at
+    }
+
+    /// token types that are locked now and scheduled to unlock
+    pub fn get_unlock_schedule(&self) -> HashMap<TokenType, U64> {
+        self.token_types.iter()
+            .filter(|(_, config)| config.is_locked())
+            .filter_map(|(token_type, config)| config.unlock_at.map(|unlock_at| (token_type, unlock_at)))
+            .collect()
+    }
+
+    pub fn get_token_types(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<JsonTokenType> {
+        let start = u64::from(from_index.unwrap_or(U64(0)));
+        self.token_types.iter()
+            .skip(start as usize)
+            .take(page_limit(limit) as usize)
+            .map(|(token_type, config)| JsonTokenType {
+                supply: U64(self.internal_supply_for_type(&token_type)),
+                is_locked: config.is_locked(),
+                token_type,
+                config,
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_token_type(&self, token_type: &TokenType) -> TokenTypeConfig {
+        self.token_types.get(token_type).expect("No token type")
+    }
+
+    pub(crate) fn internal_is_non_transferable(&self, token_type: &Option<TokenType>) -> bool {
+        token_type.as_ref()
+            .and_then(|token_type| self.token_types.get(token_type))
+            .map(|config| config.transfer_policy == TransferPolicy::NonTransferable)
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn internal_is_type_locked(&self, token_type: &Option<TokenType>) -> bool {
+        token_type.as_ref()
+            .and_then(|token_type| self.token_types.get(token_type))
+            .map(|config| config.is_locked())
+            .unwrap_or(false)
+    }
+}
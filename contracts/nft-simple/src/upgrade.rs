@@ -0,0 +1,143 @@
+use crate::*;
+use near_sdk::Gas;
+
+/// gas kept by upgrade, the rest is attached to migrate
+const GAS_FOR_UPGRADE: Gas = 20_000_000_000_000;
+const NO_DEPOSIT: Balance = 0;
+
+/// CUSTOM - bump when a deployed Contract layout changes and add that layout to VersionedContract
+/// layouts that were never deployed don't need a version, V1 converts straight to the current layout
+pub const STATE_VERSION: u8 = 2;
+/// state version is stored outside of the Contract so the layout can be known before reading it
+const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// Contract layout deployed before state versioning
+#[derive(BorshDeserialize)]
+pub struct ContractV1 {
+    pub tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+    pub tokens_by_id: LookupMap<TokenId, Token>,
+    pub token_metadata_by_id: UnorderedMap<TokenId, TokenMetadata>,
+    pub owner_id: AccountId,
+    pub extra_storage_in_bytes_per_token: StorageUsage,
+    pub metadata: LazyOption<NFTMetadata>,
+    pub supply_cap_by_type: TypeSupplyCaps,
+    pub tokens_per_type: LookupMap<TokenType, UnorderedSet<TokenId>>,
+    pub token_types_locked: UnorderedSet<TokenType>,
+    pub contract_royalty: u32,
+}
+
+/// every layout of Contract that may be in state
+#[allow(clippy::large_enum_variant)]
+pub enum VersionedContract {
+    V1(ContractV1),
+    V2(Contract),
+}
+
+impl VersionedContract {
+    pub fn read() -> Self {
+        let state_version = env::storage_read(STATE_VERSION_KEY).map(|v| v[0]).unwrap_or(1);
+        match state_version {
+            1 => VersionedContract::V1(env::state_read().expect("Failed to read V1 state")),
+            2 => VersionedContract::V2(env::state_read().expect("Failed to read V2 state")),
+            _ => env::panic(format!("Unknown state version {}", state_version).as_bytes()),
+        }
+    }
+
+    pub fn into_current(self) -> Contract {
+        match self {
+            VersionedContract::V1(old) => old.into(),
+            VersionedContract::V2(contract) => contract,
+        }
+    }
+}
+
+impl From<ContractV1> for Contract {
+    fn from(mut old: ContractV1) -> Self {
+        let mut token_types = UnorderedMap::new(StorageKey::TokenTypes.try_to_vec().unwrap());
+        for (token_type, hard_cap) in old.supply_cap_by_type {
+            let locked = old.token_types_locked.contains(&token_type);
+            token_types.insert(&token_type, &TokenTypeConfig::new(hard_cap, locked, None));
+        }
+        old.token_types_locked.clear();
+        // tokens couldn't be burned before, every default id up to the supply is taken
+        let next_token_id = old.token_metadata_by_id.len() + 1;
+        // walking every token could run out of gas, admins backfill with nft_index_owner_types
+        let owner_type_index_cursor = if old.token_metadata_by_id.is_empty() { None } else { Some(0) };
+
+        Contract {
+            tokens_per_owner: old.tokens_per_owner,
+            tokens_by_id: old.tokens_by_id,
+            token_metadata_by_id: old.token_metadata_by_id,
+            owner_id: old.owner_id,
+            extra_storage_in_bytes_per_token: old.extra_storage_in_bytes_per_token,
+            metadata: old.metadata,
+            token_types,
+            tokens_per_type: old.tokens_per_type,
+            expired_tokens: UnorderedSet::new(StorageKey::ExpiredTokens.try_to_vec().unwrap()),
+            roles_by_account: UnorderedMap::new(StorageKey::RolesByAccount.try_to_vec().unwrap()),
+            minter_token_types: LookupMap::new(StorageKey::MinterTokenTypes.try_to_vec().unwrap()),
+            public_mints_per_account: LookupMap::new(StorageKey::PublicMintsPerAccount.try_to_vec().unwrap()),
+            mint_ft_token_ids: UnorderedSet::new(StorageKey::MintFTTokenIds.try_to_vec().unwrap()),
+            beneficiaries: HashMap::new(),
+            proceeds: LookupMap::new(StorageKey::Proceeds.try_to_vec().unwrap()),
+            contract_royalty: old.contract_royalty,
+            series: UnorderedMap::new(StorageKey::Series.try_to_vec().unwrap()),
+            series_by_token: LookupMap::new(StorageKey::SeriesByToken.try_to_vec().unwrap()),
+            royalty_history: LookupMap::new(StorageKey::RoyaltyHistory.try_to_vec().unwrap()),
+            pending_royalty_reassignments: LookupMap::new(StorageKey::PendingRoyaltyReassignments.try_to_vec().unwrap()),
+            storage_deposits: LookupMap::new(StorageKey::StorageDeposits.try_to_vec().unwrap()),
+            token_attributes: LookupMap::new(StorageKey::TokenAttributes.try_to_vec().unwrap()),
+            series_attributes: LookupMap::new(StorageKey::SeriesAttributes.try_to_vec().unwrap()),
+            tokens_by_trait: LookupMap::new(StorageKey::TokensByTrait.try_to_vec().unwrap()),
+            traits_by_group: LookupMap::new(StorageKey::TraitsByGroup.try_to_vec().unwrap()),
+            children_by_token: LookupMap::new(StorageKey::ChildrenByToken.try_to_vec().unwrap()),
+            parent_by_child: LookupMap::new(StorageKey::ParentByChild.try_to_vec().unwrap()),
+            token_users: LookupMap::new(StorageKey::TokenUsers.try_to_vec().unwrap()),
+            approval_conditions: LookupMap::new(StorageKey::ApprovalConditions.try_to_vec().unwrap()),
+            operators_by_owner: LookupMap::new(StorageKey::OperatorsByOwner.try_to_vec().unwrap()),
+            paused: vec![],
+            frozen_tokens: LookupMap::new(StorageKey::FrozenTokens.try_to_vec().unwrap()),
+            next_token_id,
+            tokens_per_owner_type: LookupMap::new(StorageKey::TokensPerOwnerType.try_to_vec().unwrap()),
+            owner_type_index_cursor,
+            nest_contract_ids: UnorderedSet::new(StorageKey::NestContractIds.try_to_vec().unwrap()),
+            mint_storage_by_token: LookupMap::new(StorageKey::MintStorageByToken.try_to_vec().unwrap()),
+        }
+    }
+}
+
+pub(crate) fn write_state_version() {
+    env::storage_write(STATE_VERSION_KEY, &[STATE_VERSION]);
+}
+
+#[near_bindgen]
+impl Contract {
+    /// CUSTOM - only owner - deploys new code and migrates state in the same receipt
+    /// admins can't upgrade, new code could bypass every role check and timelock
+    /// the input of this call is the raw wasm of the new contract, not JSON
+    pub fn upgrade(&self) -> Promise {
+        self.assert_owner();
+        let code = env::input().expect("No code to deploy");
+        Promise::new(env::current_account_id())
+            .deploy_contract(code)
+            .function_call(
+                b"migrate".to_vec(),
+                vec![],
+                NO_DEPOSIT,
+                env::prepaid_gas() - env::used_gas() - GAS_FOR_UPGRADE,
+            )
+    }
+
+    /// called by upgrade with the new code, reads any previous layout and writes the current one
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate() -> Self {
+        let contract = VersionedContract::read().into_current();
+        write_state_version();
+        contract
+    }
+
+    pub fn version(&self) -> String {
+        format!("{}-state.{}", env!("CARGO_PKG_VERSION"), STATE_VERSION)
+    }
+}
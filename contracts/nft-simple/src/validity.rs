@@ -0,0 +1,118 @@
+use crate::*;
+
+/// CUSTOM - how TokenMetadata starts_at / expires_at are used for a token_type
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ValidityPolicy {
+    /// starts_at and expires_at are informational only
+    Ignore,
+    /// transfers, approvals and payouts are only allowed inside the validity window
+    Enforce,
+    /// Enforce, and nft_cleanup_expired marks expired tokens
+    EnforceAndMarkExpired,
+    /// Enforce, and nft_cleanup_expired burns expired tokens
+    EnforceAndBurnExpired,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// anyone can clean up expired tokens according to the ValidityPolicy of their token_type
+    /// attach a deposit to cover storage of marked tokens, burned tokens refund their holders
+    #[payable]
+    pub fn nft_cleanup_expired(&mut self, token_ids: Vec<TokenId>) -> Vec<TokenId> {
+        let initial_storage_usage = env::storage_usage();
+        let mut cleaned_up = vec![];
+        for token_id in token_ids {
+            let token = if let Some(token) = self.tokens_by_id.get(&token_id) {
+                token
+            } else {
+                continue;
+            };
+            if !self.internal_is_expired(&token_id) {
+                continue;
+            }
+            match self.internal_validity_policy(&token.token_type) {
+                ValidityPolicy::EnforceAndMarkExpired => {
+                    if self.expired_tokens.insert(&token_id) {
+                        cleaned_up.push(token_id);
+                    }
+                }
+                ValidityPolicy::EnforceAndBurnExpired => {
+                    // nested tokens and parents are burned after they are detached
+                    if self.children_by_token.get(&token_id).is_some() || self.nft_parent(token_id.clone(), None).is_some() {
+                        continue;
+                    }
+                    // frozen tokens wait for their recovery, see nft_freeze
+                    if self.frozen_tokens.get(&token_id).is_some() {
+                        continue;
+                    }
+                    self.internal_burn(token_id.clone(), token, Some(env::predecessor_account_id()));
+                    cleaned_up.push(token_id);
+                }
+                _ => {}
+            }
+        }
+
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_charge_storage(storage_used);
+
+        cleaned_up
+    }
+
+    /// only type manager
+
+    pub fn set_token_type_validity_policy(&mut self, token_type: TokenType, validity_policy: ValidityPolicy) {
+        self.assert_role(Role::TypeManager);
+        let mut config = self.internal_token_type(&token_type);
+        config.validity_policy = validity_policy;
+        self.token_types.insert(&token_type, &config);
+    }
+
+    /// views
+
+    /// true if the current block time is inside the token's starts_at / expires_at window
+    pub fn nft_is_valid(&self, token_id: TokenId) -> bool {
+        assert!(self.tokens_by_id.get(&token_id).is_some(), "No token");
+        !self.expired_tokens.contains(&token_id) && self.internal_is_in_window(&token_id)
+    }
+
+    pub fn nft_expired_tokens(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<TokenId> {
+        let start = u64::from(from_index.unwrap_or(U64(0)));
+        self.expired_tokens.iter()
+            .skip(start as usize)
+            .take(page_limit(limit) as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_validity_policy(&self, token_type: &Option<TokenType>) -> ValidityPolicy {
+        token_type.as_ref()
+            .map(|token_type| self.internal_token_type(token_type).validity_policy)
+            .unwrap_or(ValidityPolicy::Ignore)
+    }
+
+    pub(crate) fn internal_is_in_window(&self, token_id: &TokenId) -> bool {
+        let metadata = self.internal_token_metadata(token_id).expect("No token");
+        let now = env::block_timestamp() / 1_000_000;
+        metadata.starts_at.map(|starts_at| now >= starts_at).unwrap_or(true)
+            && metadata.expires_at.map(|expires_at| now < expires_at).unwrap_or(true)
+    }
+
+    pub(crate) fn internal_is_expired(&self, token_id: &TokenId) -> bool {
+        let metadata = self.internal_token_metadata(token_id).expect("No token");
+        let now = env::block_timestamp() / 1_000_000;
+        metadata.expires_at.map(|expires_at| now >= expires_at).unwrap_or(false)
+    }
+
+    /// panics outside the validity window if the token_type enforces it
+    pub(crate) fn assert_token_valid(&self, token_id: &TokenId, token: &Token) {
+        if self.internal_validity_policy(&token.token_type) == ValidityPolicy::Ignore {
+            return;
+        }
+        assert!(
+            !self.expired_tokens.contains(token_id) && self.internal_is_in_window(token_id),
+            "Token is not valid at this time"
+        );
+    }
+}
@@ -79,9 +79,10 @@ export const loadItems = (account) => async ({ update, getState }) => {
         });
     }
     
-    const saleTokens = await contractAccount.viewFunction(contractId, 'nft_tokens_batch', {
+    // nft_tokens_batch returns null for token ids that don't exist (e.g. burned)
+    const saleTokens = (await contractAccount.viewFunction(contractId, 'nft_tokens_batch', {
         token_ids: sales.filter(({ nft_contract_id }) => nft_contract_id === contractId).map(({ token_id }) => token_id)
-    });
+    })).filter((token) => !!token);
     // merge sale listing with nft token data
     for (let i = 0; i < sales.length; i++) {
         const { token_id } = sales[i];
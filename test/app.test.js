@@ -54,6 +54,21 @@ const fungibleId = 'fungible.' + contractId;
 /// the market contract
 const marketId = 'market.' + contractId;
 
+/// yoctoNEAR the signer paid for gas across every receipt of a call
+const tokensBurnt = ({ transaction_outcome, receipts_outcome }) => receipts_outcome.reduce(
+	(sum, { outcome }) => sum.add(new BN(outcome.tokens_burnt)),
+	new BN(transaction_outcome.outcome.tokens_burnt)
+);
+/// true if the call panicked
+const callFails = async (call) => {
+	try {
+		await call();
+		return false;
+	} catch (e) {
+		return true;
+	}
+};
+
 describe('deploy contract ' + contractName, () => {
 
 	let alice, aliceId, bob, bobId,
@@ -97,6 +112,19 @@ describe('deploy contract ' + contractName, () => {
 			gas: GAS
 		});
 
+		// alice and bob mint tokens in the tests below
+		for (const account_id of [aliceId, bobId]) {
+			await contractAccount.functionCall({
+				contractId,
+				methodName: 'grant_role',
+				args: {
+					account_id,
+					role: 'Minter',
+				},
+				gas: GAS
+			});
+		}
+
 		/// create or get fungibleAccount and deploy ft.wasm (if not already deployed)
 		fungibleAccount = await createOrInitAccount(fungibleId, GUESTS_ACCOUNT_SECRET);
 		const fungibleAccountState = await fungibleAccount.state();
@@ -902,4 +930,424 @@ describe('deploy contract ' + contractName, () => {
 		});
 	});
 
-});
\ No newline at end of file
+
+	/// vaults
+
+	/// deploys ft.wasm in vault mode for a new token of alice, she sends it in and receives all shares
+	const vaultWithToken = async (name, reserve_price) => {
+		const vaultId = `${name}-${now}.${contractId}`;
+		const vaultAccount = await createOrInitAccount(vaultId, GUESTS_ACCOUNT_SECRET);
+		const newVaultArgs = {
+			owner_id: aliceId,
+			shares: '1000',
+			version: '1',
+			name: 'Test Vault',
+			symbol: 'TV',
+			reference: '',
+			reference_hash: '',
+			decimals: 0,
+			nft_contract_id: contractId,
+			reserve_price,
+		};
+		await vaultAccount.signAndSendTransaction({
+			receiverId: vaultId,
+			actions: [
+				deployContract(fs.readFileSync('./out/ft.wasm')),
+				functionCall('new_vault', newVaultArgs, GAS)
+			]
+		});
+
+		const token_id = `${name}:${now}`;
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_mint',
+			args: { token_id, metadata },
+			gas: GAS,
+			attachedDeposit: parseNearAmount('1')
+		});
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_transfer_call',
+			args: {
+				receiver_id: vaultId,
+				token_id,
+				approval_id: 0,
+				msg: '',
+			},
+			gas: GAS,
+			attachedDeposit: 1
+		});
+		const vault = await alice.viewFunction(vaultId, 'get_vault');
+		expect(vault.state).toEqual('Active');
+		expect(await alice.viewFunction(vaultId, 'ft_balance_of', { account_id: aliceId })).toEqual('1000');
+		return { vaultId, token_id };
+	};
+
+	test('alice redeems the NFT of a vault with all shares', async () => {
+		const { vaultId, token_id } = await vaultWithToken('vault-redeem', parseNearAmount('1'));
+
+		await alice.functionCall({
+			contractId: vaultId,
+			methodName: 'redeem',
+			args: {},
+			gas: GAS,
+			attachedDeposit: 1
+		});
+		const token = await contract.nft_token({ token_id });
+		expect(token.owner_id).toEqual(aliceId);
+		const vault = await alice.viewFunction(vaultId, 'get_vault');
+		expect(vault.state).toEqual('Redeemed');
+		expect(await alice.viewFunction(vaultId, 'ft_total_supply')).toEqual('0');
+	});
+
+	test('bob buys the NFT out of a vault and alice claims the proceeds', async () => {
+		const reserve_price = parseNearAmount('1');
+		const { vaultId, token_id } = await vaultWithToken('vault-buyout', reserve_price);
+
+		await bob.functionCall({
+			contractId: vaultId,
+			methodName: 'buyout',
+			args: {},
+			gas: GAS,
+			attachedDeposit: reserve_price
+		});
+		const token = await contract.nft_token({ token_id });
+		expect(token.owner_id).toEqual(bobId);
+		const vault = await alice.viewFunction(vaultId, 'get_vault');
+		expect(vault.state).toEqual('BoughtOut');
+		expect(vault.proceeds).toEqual(reserve_price);
+
+		const aliceBalanceBefore = await getAccountBalance(aliceId);
+		await alice.functionCall({
+			contractId: vaultId,
+			methodName: 'claim_buyout_proceeds',
+			args: {},
+			gas: GAS,
+			attachedDeposit: 1
+		});
+		const aliceBalanceAfter = await getAccountBalance(aliceId);
+		/// alice held all shares, she gets close to 1 N (minus gas)
+		expect(new BN(aliceBalanceAfter.total).sub(new BN(aliceBalanceBefore.total)).gt(new BN(parseNearAmount('0.99')))).toEqual(true);
+	});
+
+	/// burns
+
+	test('bob burns a token alice minted for him, bob gets his approval deposit and alice the mint storage', async () => {
+		const token_id = `burn:${now}`;
+		const cost = await alice.viewFunction(contractId, 'nft_mint_storage_cost', {
+			token_id,
+			metadata,
+			receiver_id: bobId,
+			payer_id: aliceId,
+		});
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_mint',
+			args: {
+				token_id,
+				metadata,
+				receiver_id: bobId,
+			},
+			gas: GAS,
+			attachedDeposit: cost
+		});
+		const approvalCost = await bob.viewFunction(contractId, 'nft_approve_storage_cost', {
+			token_id,
+			account_id: aliceId,
+		});
+		// exact deposit, nothing is refunded by nft_approve
+		await bob.functionCall({
+			contractId,
+			methodName: 'nft_approve',
+			args: {
+				token_id,
+				account_id: aliceId,
+			},
+			gas: GAS,
+			attachedDeposit: approvalCost
+		});
+
+		const aliceBalanceBefore = await getAccountBalance(aliceId);
+		const bobBalanceBefore = await getAccountBalance(bobId);
+		const outcome = await bob.functionCall({
+			contractId,
+			methodName: 'nft_burn',
+			args: { token_id },
+			gas: GAS,
+			attachedDeposit: 1
+		});
+		const aliceBalanceAfter = await getAccountBalance(aliceId);
+		const bobBalanceAfter = await getAccountBalance(bobId);
+
+		const expected = new BN(approvalCost).sub(new BN('1')).sub(tokensBurnt(outcome));
+		expect(new BN(bobBalanceAfter.total).sub(new BN(bobBalanceBefore.total)).toString()).toEqual(expected.toString());
+		// alice gets back what the burn freed, up to what she paid
+		const aliceRefund = new BN(aliceBalanceAfter.total).sub(new BN(aliceBalanceBefore.total));
+		expect(aliceRefund.gt(new BN('0'))).toEqual(true);
+		expect(aliceRefund.lte(new BN(cost))).toEqual(true);
+		const token = await contract.nft_token({ token_id });
+		expect(token).toEqual(null);
+	});
+
+	/// payouts
+
+	test('nft_payout splits the whole balance', async () => {
+		const token_id = `payout:${now}`;
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_mint',
+			args: {
+				token_id,
+				metadata,
+				perpetual_royalties: {
+					'a1.testnet': 333,
+					'a2.testnet': 777,
+				},
+			},
+			gas: GAS,
+			attachedDeposit: parseNearAmount('0.1')
+		});
+		// odd amount, royalties round down and the owner gets the remainder
+		const balance = '1000000000000000000000007';
+		const { payout } = await alice.viewFunction(contractId, 'nft_payout', {
+			token_id,
+			balance,
+			max_len_payout: 10,
+		});
+		console.log('\n\n nft_payout', payout, '\n\n');
+		const sum = Object.values(payout).reduce((sum, amount) => sum.add(new BN(amount)), new BN('0'));
+		expect(sum.toString()).toEqual(balance);
+		// 2 royalties, the contract royalty and alice
+		expect(Object.keys(payout).length).toEqual(4);
+	});
+
+	/// storage quotes
+
+	test('alice mints with her storage balance, the charge equals nft_mint_storage_cost', async () => {
+		const token_id = `quote:${now}`;
+		const perpetual_royalties = {
+			'a1.testnet': 333,
+			'a2.testnet': 777,
+		};
+		await alice.functionCall({
+			contractId,
+			methodName: 'storage_deposit',
+			args: {},
+			gas: GAS,
+			attachedDeposit: parseNearAmount('0.5')
+		});
+		const cost = await alice.viewFunction(contractId, 'nft_mint_storage_cost', {
+			token_id,
+			metadata,
+			perpetual_royalties,
+			receiver_id: aliceId,
+		});
+		console.log('\n\n nft_mint_storage_cost', cost, '\n\n');
+		const balanceBefore = await alice.viewFunction(contractId, 'storage_balance_of', { account_id: aliceId });
+		// no deposit, the whole charge comes from the storage balance
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_mint',
+			args: {
+				token_id,
+				metadata,
+				perpetual_royalties,
+			},
+			gas: GAS,
+		});
+		const balanceAfter = await alice.viewFunction(contractId, 'storage_balance_of', { account_id: aliceId });
+		expect(new BN(balanceBefore.total).sub(new BN(balanceAfter.total)).toString()).toEqual(cost);
+	});
+
+	/// pauses
+
+	test('purchases are refunded while the market or NFT transfers are paused', async () => {
+		const token_id = `paused:${now}`;
+		const price = parseNearAmount('1');
+		await alice.functionCall({
+			contractId: marketId,
+			methodName: 'storage_deposit',
+			args: {},
+			gas: GAS,
+			attachedDeposit: parseNearAmount('0.02')
+		});
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_mint',
+			args: {
+				token_id,
+				metadata,
+			},
+			gas: GAS,
+			attachedDeposit: parseNearAmount('0.1')
+		});
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_approve',
+			args: {
+				token_id,
+				account_id: marketId,
+				msg: JSON.stringify({ sale_conditions: { near: price } })
+			},
+			gas: GAS,
+			attachedDeposit: parseNearAmount('0.01')
+		});
+		const offer = () => bob.functionCall({
+			contractId: marketId,
+			methodName: 'offer',
+			args: {
+				nft_contract_id: contractId,
+				token_id,
+			},
+			gas: GAS,
+			attachedDeposit: price
+		});
+
+		// market purchases paused, the offer panics and keeps nothing
+		await contractAccount.functionCall({
+			contractId: marketId,
+			methodName: 'pause',
+			args: { flags: ['Purchases'] },
+			gas: GAS
+		});
+		expect(await callFails(offer)).toEqual(true);
+		await contractAccount.functionCall({
+			contractId: marketId,
+			methodName: 'unpause',
+			args: { flags: ['Purchases'] },
+			gas: GAS
+		});
+
+		// NFT transfers paused, nft_transfer_payout fails and the market refunds bob
+		await contractAccount.functionCall({
+			contractId,
+			methodName: 'pause',
+			args: { flags: ['Transfers'] },
+			gas: GAS
+		});
+		const bobBalanceBefore = await getAccountBalance(bobId);
+		const outcome = await offer();
+		const bobBalanceAfter = await getAccountBalance(bobId);
+		await contractAccount.functionCall({
+			contractId,
+			methodName: 'unpause',
+			args: { flags: ['Transfers'] },
+			gas: GAS
+		});
+		expect(new BN(bobBalanceBefore.total).sub(new BN(bobBalanceAfter.total)).toString()).toEqual(tokensBurnt(outcome).toString());
+		const token = await contract.nft_token({ token_id });
+		expect(token.owner_id).toEqual(aliceId);
+	});
+
+	/// freezes
+
+	test('frozen tokens block transfers, approvals, burns and rentals', async () => {
+		const token_id = `frozen:${now}`;
+		const rental_token_id = `frozen-rental:${now}`;
+		const price_per_period = parseNearAmount('0.1');
+		for (const id of [token_id, rental_token_id]) {
+			await alice.functionCall({
+				contractId,
+				methodName: 'nft_mint',
+				args: {
+					token_id: id,
+					metadata,
+				},
+				gas: GAS,
+				attachedDeposit: parseNearAmount('0.1')
+			});
+		}
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_approve',
+			args: {
+				token_id: rental_token_id,
+				account_id: marketId,
+				msg: JSON.stringify({ price_per_period, period: '3600000' })
+			},
+			gas: GAS,
+			attachedDeposit: parseNearAmount('0.01')
+		});
+		for (const id of [token_id, rental_token_id]) {
+			await contractAccount.functionCall({
+				contractId,
+				methodName: 'nft_freeze',
+				args: {
+					token_id: id,
+					reason: 'reported stolen',
+				},
+				gas: GAS
+			});
+		}
+
+		expect(await callFails(() => alice.functionCall({
+			contractId,
+			methodName: 'nft_transfer',
+			args: {
+				receiver_id: bobId,
+				token_id,
+				approval_id: 0,
+			},
+			gas: GAS,
+			attachedDeposit: 1
+		}))).toEqual(true);
+		expect(await callFails(() => alice.functionCall({
+			contractId,
+			methodName: 'nft_approve',
+			args: {
+				token_id,
+				account_id: bobId,
+			},
+			gas: GAS,
+			attachedDeposit: parseNearAmount('0.01')
+		}))).toEqual(true);
+		expect(await callFails(() => alice.functionCall({
+			contractId,
+			methodName: 'nft_burn',
+			args: { token_id },
+			gas: GAS,
+			attachedDeposit: 1
+		}))).toEqual(true);
+
+		// nft_set_user_payout fails and the market refunds the rent and storage deposit
+		const bobBalanceBefore = await getAccountBalance(bobId);
+		const outcome = await bob.functionCall({
+			contractId: marketId,
+			methodName: 'rent',
+			args: {
+				nft_contract_id: contractId,
+				token_id: rental_token_id,
+				periods: 1,
+			},
+			gas: GAS,
+			// price_per_period + STORAGE_FOR_RENTAL_USER
+			attachedDeposit: new BN(price_per_period).add(new BN(parseNearAmount('0.0035'))).toString()
+		});
+		const bobBalanceAfter = await getAccountBalance(bobId);
+		expect(new BN(bobBalanceBefore.total).sub(new BN(bobBalanceAfter.total)).toString()).toEqual(tokensBurnt(outcome).toString());
+		const user = await bob.viewFunction(contractId, 'nft_user_of', { token_id: rental_token_id });
+		expect(user).toEqual(null);
+
+		// unfrozen, the transfer goes through
+		await contractAccount.functionCall({
+			contractId,
+			methodName: 'nft_unfreeze',
+			args: { token_id },
+			gas: GAS
+		});
+		await alice.functionCall({
+			contractId,
+			methodName: 'nft_transfer',
+			args: {
+				receiver_id: bobId,
+				token_id,
+				approval_id: 0,
+			},
+			gas: GAS,
+			attachedDeposit: 1
+		});
+		const token = await contract.nft_token({ token_id });
+		expect(token.owner_id).toEqual(bobId);
+	});
+
+});